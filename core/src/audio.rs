@@ -0,0 +1,289 @@
+use std::cell::RefCell;
+use std::f32::consts::PI;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// Sample rate that sound is rendered at.
+pub const SAMPLE_RATE: u32 = 44100;
+
+/// Frequency of the classic CHIP-8 beep.
+pub const BEEP_FREQUENCY_HZ: f32 = 440.0;
+
+/// What the beeper plays while the sound timer is running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tone {
+    /// The classic CHIP-8 beep.
+    Beep,
+    /// An XO-CHIP 1-bit audio pattern, played at a rate set by the pitch register.
+    Pattern { samples: [u8; 16], pitch: u8 }
+}
+
+impl Tone {
+    /// Gets the tone for an optional audio pattern and a pitch register value.
+    pub fn new(pattern: Option<[u8; 16]>, pitch: u8) -> Self {
+        match pattern {
+            Some(samples) => Tone::Pattern {
+                samples,
+                pitch
+            },
+            None => Tone::Beep
+        }
+    }
+}
+
+/// Gets the rate in bits per second that an XO-CHIP audio pattern plays at for a pitch
+/// register value. The default pitch of 64 plays at 4000 bits per second.
+pub fn pattern_rate(pitch: u8) -> f32 {
+    4000.0 * 2f32.powf((pitch as f32 - 64.0) / 48.0)
+}
+
+/// Names accepted by [`Waveform::from_name`].
+pub const WAVEFORM_NAMES: [&str; 3] = ["square", "sine", "triangle"];
+
+/// Shape of the beep's wave.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Waveform {
+    /// A harsh buzz like the COSMAC VIP's beeper.
+    Square,
+    Sine,
+    Triangle
+}
+
+impl Waveform {
+    /// Looks up a waveform by one of the names in [`WAVEFORM_NAMES`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "square" => Some(Waveform::Square),
+            "sine" => Some(Waveform::Sine),
+            "triangle" => Some(Waveform::Triangle),
+            _ => None
+        }
+    }
+
+    /// Gets the height of the wave, from -1 to 1, at a phase from 0 to 1.
+    fn sample_at(self, phase: f32) -> f32 {
+        match self {
+            Waveform::Square => if phase < 0.5 { 1.0 } else { -1.0 },
+            Waveform::Sine => (2.0 * PI * phase).sin(),
+            Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs()
+        }
+    }
+}
+
+/// How the beeper sounds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BeeperSettings {
+    /// Shape of the classic beep. XO-CHIP audio patterns always play as they are.
+    pub waveform: Waveform,
+    /// Frequency of the classic beep, in Hz.
+    pub frequency: f32,
+    /// Loudness, from 0 to 1.
+    pub volume: f32,
+    /// Time the sound takes to fade in and out, so it doesn't pop when it starts and stops.
+    pub ramp: Duration
+}
+
+impl Default for BeeperSettings {
+    /// A 440 Hz sine at full volume with a 5 ms ramp.
+    fn default() -> Self {
+        BeeperSettings {
+            waveform: Waveform::Sine,
+            frequency: BEEP_FREQUENCY_HZ,
+            volume: 1.0,
+            ramp: Duration::from_millis(5)
+        }
+    }
+}
+
+/// Renders tones as samples. The phase of the wave is kept between calls so the sound stays
+/// smooth when it's produced in chunks, and the volume ramps up and down as sound starts and stops.
+#[derive(Debug, Clone)]
+pub struct Beeper {
+    settings: BeeperSettings,
+    sample_rate: u32,
+    /// Phase of the beep from 0 to 1, or the current bit of a 128-bit pattern.
+    position: f32,
+    /// The tone playing, or fading out after it stopped.
+    tone: Tone,
+    playing: bool,
+    /// Current volume of the envelope, from 0 to 1.
+    gain: f32
+}
+
+impl Beeper {
+    pub fn new(settings: BeeperSettings, sample_rate: u32) -> Self {
+        Beeper {
+            settings,
+            sample_rate,
+            position: 0.0,
+            tone: Tone::Beep,
+            playing: false,
+            gain: 0.0
+        }
+    }
+
+    /// Starts playing a tone, or fades out if it's `None`.
+    pub fn play(&mut self, tone: Option<Tone>) {
+        if let Some(tone) = tone {
+            if tone != self.tone {
+                self.position = 0.0;
+            }
+
+            self.tone = tone;
+        }
+
+        self.playing = tone.is_some();
+    }
+
+    /// Gets the next sample, from -1 to 1.
+    pub fn next_sample(&mut self) -> f32 {
+        let ramp_samples = self.settings.ramp.as_secs_f32() * self.sample_rate as f32;
+        let target = if self.playing { 1.0 } else { 0.0 };
+
+        self.gain = if ramp_samples < 1.0 {
+            target
+        } else if self.gain < target {
+            (self.gain + 1.0 / ramp_samples).min(target)
+        } else {
+            (self.gain - 1.0 / ramp_samples).max(target)
+        };
+
+        if self.gain == 0.0 {
+            return 0.0;
+        }
+
+        let wave = match self.tone {
+            Tone::Beep => {
+                self.position = (self.position + self.settings.frequency / self.sample_rate as f32) % 1.0;
+
+                self.settings.waveform.sample_at(self.position)
+            }
+            Tone::Pattern { samples, pitch } => {
+                self.position = (self.position + pattern_rate(pitch) / self.sample_rate as f32) % 128.0;
+
+                let bit = self.position as usize;
+
+                if (samples[bit / 8] >> (7 - bit % 8)) & 1 > 0 { 0.5 } else { -0.5 }
+            }
+        };
+
+        wave * self.settings.volume * self.gain
+    }
+}
+
+/// Receives sound from the CPU. The CPU calls it when the sound timer starts or stops, and when
+/// the tone changes while the timer is running.
+///
+/// Times are emulated time since the CPU was created, not wall clock time.
+pub trait AudioSink {
+    /// Starts playing a tone, or switches to it if a tone is already playing.
+    fn start(&mut self, time: Duration, tone: Tone);
+
+    /// Stops playing.
+    fn stop(&mut self, time: Duration);
+}
+
+/// Discards all sound. CPUs use this until they're given another sink.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NullSink;
+
+impl AudioSink for NullSink {
+    fn start(&mut self, _time: Duration, _tone: Tone) {}
+
+    fn stop(&mut self, _time: Duration) {}
+}
+
+/// Sends sound to two sinks, e.g. a speaker and a recording.
+pub struct TeeSink {
+    first: Box<dyn AudioSink>,
+    second: Box<dyn AudioSink>
+}
+
+impl TeeSink {
+    pub fn new(first: Box<dyn AudioSink>, second: Box<dyn AudioSink>) -> Self {
+        TeeSink {
+            first,
+            second
+        }
+    }
+}
+
+impl AudioSink for TeeSink {
+    fn start(&mut self, time: Duration, tone: Tone) {
+        self.first.start(time, tone);
+        self.second.start(time, tone);
+    }
+
+    fn stop(&mut self, time: Duration) {
+        self.first.stop(time);
+        self.second.stop(time);
+    }
+}
+
+/// A tone starting or stopping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioEvent {
+    pub time: Duration,
+    /// The tone that started, or `None` if sound stopped.
+    pub tone: Option<Tone>
+}
+
+/// Records when sound starts and stops, so tests can check when a program beeped.
+///
+/// Clones share the same recording, so a clone can be kept to read the events after the
+/// sink has been given to a CPU.
+#[derive(Debug, Clone, Default)]
+pub struct RecordingSink {
+    events: Rc<RefCell<Vec<AudioEvent>>>
+}
+
+impl RecordingSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gets a copy of the events recorded so far.
+    pub fn get_events(&self) -> Vec<AudioEvent> {
+        self.events.borrow().clone()
+    }
+
+    /// Gets the start and end of each stretch of sound, with `None` as the end if it's still playing.
+    pub fn get_intervals(&self) -> Vec<(Duration, Option<Duration>)> {
+        let mut intervals = Vec::new();
+        let mut started = None;
+
+        for event in self.events.borrow().iter() {
+            match (event.tone, started) {
+                (Some(_), None) => started = Some(event.time),
+                (None, Some(start)) => {
+                    intervals.push((start, Some(event.time)));
+                    started = None;
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(start) = started {
+            intervals.push((start, None));
+        }
+
+        intervals
+    }
+
+    fn push(&self, time: Duration, tone: Option<Tone>) {
+        self.events.borrow_mut().push(AudioEvent {
+            time,
+            tone
+        });
+    }
+}
+
+impl AudioSink for RecordingSink {
+    fn start(&mut self, time: Duration, tone: Tone) {
+        self.push(time, Some(tone));
+    }
+
+    fn stop(&mut self, time: Duration) {
+        self.push(time, None);
+    }
+}
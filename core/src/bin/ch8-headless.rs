@@ -0,0 +1,293 @@
+//! Runs a ROM without a window or audio device and prints the final machine state, for scripted
+//! runs and regression tests on build machines.
+
+use ch8_core::audio::{self, BeeperSettings, Waveform};
+use ch8_core::conformance;
+use ch8_core::cpu::{self, Cpu};
+use ch8_core::debugger::{Condition, StopReason};
+use ch8_core::headless::{self, InputScript, RunOptions};
+use ch8_core::quirks::{self, Quirks};
+use ch8_core::screenshot::Screenshot;
+use ch8_core::wav::WavRecorder;
+
+use std::env;
+use std::fs;
+use std::process;
+use std::time::Duration;
+
+const DEFAULT_FRAMES: u32 = 600;
+
+const USAGE: &str = "\
+Usage: ch8-headless ROM [options]
+       ch8-headless --conformance
+
+Options:
+  --frames N         frames to run for (default 600, 10 seconds)
+  --preset NAME      quirks preset: vip, chip48, schip or modern
+  --ipf N            instructions per frame (default 8)
+  --seed N           seed for the random number generator
+  --load-address ADDR hex address to load the ROM at and start from (default 200, 600 for ETI-660)
+  --input FILE       key input script, with lines like `60 tap 5`
+  --until-pc ADDR    stop before executing the instruction at a hex address
+  --until COND       stop when a condition becomes true, e.g. \"v3 == 5\"
+  --memory START-END also dump memory in a hex range, e.g. 200-2FF
+  --no-display       don't print the display
+  --screenshot FILE  save the display as a plain PBM image
+  --wav FILE         record the sound to a WAV file, timed by the frames run
+  --waveform NAME    shape of the recorded beep: square, sine or triangle (default sine)
+  --frequency HZ     frequency of the recorded beep (default 440)
+  --volume N         volume of the recording from 0 to 1 (default 1)
+  --ramp MS          milliseconds to fade the sound in and out over (default 5)
+
+Conformance:
+  --conformance      run the built-in test ROMs under each quirks preset and compare the
+                     result screens with the built-in references
+
+Exits with 2 if the CPU halted on a fault, or if a conformance check failed.";
+
+struct Args {
+    rom: String,
+    preset: Option<String>,
+    instructions_per_frame: Option<u32>,
+    seed: Option<u64>,
+    load_address: Option<usize>,
+    options: RunOptions,
+    memory: Option<(usize, usize)>,
+    print_display: bool,
+    screenshot: Option<String>,
+    wav: Option<String>,
+    beeper: BeeperSettings,
+    conformance: bool
+}
+
+fn main() {
+    let args = match parse_args(env::args().skip(1).collect()) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            process::exit(1);
+        }
+    };
+
+    if args.conformance {
+        run_conformance();
+    }
+
+    let rom = fs::read(&args.rom).unwrap_or_else(|err| {
+        eprintln!("Couldn't read {}: {}", args.rom, err);
+        process::exit(1);
+    });
+
+    let mut cpu = Cpu::new();
+
+    cpu.init();
+
+    if let Some(name) = args.preset.as_deref() {
+        cpu.set_quirks(Quirks::from_preset_name(name).unwrap_or_default());
+    }
+
+    if let Some(instructions) = args.instructions_per_frame {
+        cpu.set_instructions_per_frame(instructions);
+    }
+
+    if let Some(seed) = args.seed {
+        cpu.seed_rng(seed);
+    }
+
+    for warning in cpu::check_program(&rom) {
+        eprintln!("Warning: {}", warning);
+    }
+
+    if let Err(err) = cpu.load_program_at(&rom, args.load_address.unwrap_or(cpu::PROGRAM_START)) {
+        eprintln!("Couldn't load {}: {}", args.rom, err);
+        process::exit(1);
+    }
+
+    let recorder = args.wav.as_deref().map(|path| {
+        let recorder = WavRecorder::create(path, args.beeper).unwrap_or_else(|err| {
+            eprintln!("Couldn't create {}: {}", path, err);
+            process::exit(1);
+        });
+
+        cpu.set_audio_sink(Box::new(recorder.clone()));
+
+        (path, recorder)
+    });
+
+    let result = headless::run(&mut cpu, &args.options);
+
+    if let Some((path, recorder)) = recorder {
+        if let Err(err) = recorder.finish(cpu.get_elapsed_time()) {
+            eprintln!("Couldn't write {}: {}", path, err);
+            process::exit(1);
+        }
+    }
+
+    match &result.stop {
+        Some(reason) => println!("Stopped after {} frames: {}", result.frames, reason),
+        None => println!("Ran {} frames", result.frames)
+    }
+
+    print!("{}", headless::format_registers(&cpu));
+
+    if args.print_display {
+        println!();
+        print!("{}", headless::format_display(cpu.get_display()));
+    }
+
+    if let Some((start, end)) = args.memory {
+        println!();
+        print!("{}", headless::format_memory(&cpu, start..=end));
+    }
+
+    if let Some(path) = args.screenshot.as_deref() {
+        if let Err(err) = fs::write(path, Screenshot::from_display(cpu.get_display()).to_pbm()) {
+            eprintln!("Couldn't write {}: {}", path, err);
+            process::exit(1);
+        }
+    }
+
+    if let Some(StopReason::Fault(_)) = result.stop {
+        process::exit(2);
+    }
+}
+
+/// Runs the conformance checks, prints a report and exits.
+fn run_conformance() -> ! {
+    let results = conformance::run_checks();
+    let mut failed = false;
+
+    for result in results.iter() {
+        println!("{:<12} {:<8} {}", result.rom, result.preset, result.outcome);
+
+        if let conformance::Outcome::Fail(diff) = &result.outcome {
+            print!("{}", diff);
+        }
+
+        failed |= result.outcome.is_failure();
+    }
+
+    process::exit(if failed { 2 } else { 0 });
+}
+
+fn parse_args(args: Vec<String>) -> Result<Args, String> {
+    let mut rom = None;
+    let mut parsed = Args {
+        rom: String::new(),
+        preset: None,
+        instructions_per_frame: None,
+        seed: None,
+        load_address: None,
+        options: RunOptions {
+            frames: DEFAULT_FRAMES,
+            ..RunOptions::default()
+        },
+        memory: None,
+        print_display: true,
+        screenshot: None,
+        wav: None,
+        beeper: BeeperSettings::default(),
+        conformance: false
+    };
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("{} needs a value", name));
+
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            "--frames" => parsed.options.frames = value("--frames")?.parse().map_err(|_| "--frames must be a number")?,
+            "--preset" => {
+                let name = value("--preset")?;
+
+                if Quirks::from_preset_name(&name).is_none() {
+                    return Err(format!("Unknown preset {}, expected one of: {}", name, quirks::PRESET_NAMES.join(", ")));
+                }
+
+                parsed.preset = Some(name);
+            }
+            "--ipf" => {
+                parsed.instructions_per_frame = Some(
+                    value("--ipf")?
+                        .parse()
+                        .ok()
+                        .filter(|&ipf| ipf > 0)
+                        .ok_or("--ipf must be a number above 0")?
+                );
+            }
+            "--seed" => parsed.seed = Some(value("--seed")?.parse().map_err(|_| "--seed must be a number")?),
+            "--load-address" => parsed.load_address = Some(parse_hex(&value("--load-address")?)?),
+            "--input" => {
+                let path = value("--input")?;
+                let text = fs::read_to_string(&path).map_err(|err| format!("Couldn't read {}: {}", path, err))?;
+
+                parsed.options.input = InputScript::parse(&text).map_err(|err| format!("{}: {}", path, err))?;
+            }
+            "--until-pc" => {
+                let addr = parse_hex(&value("--until-pc")?)?;
+
+                parsed.options.stop_addresses.push(addr);
+            }
+            "--until" => {
+                let text = value("--until")?;
+                let condition = Condition::parse(&text).ok_or_else(|| format!("Couldn't understand the condition {}", text))?;
+
+                parsed.options.stop_conditions.push(condition);
+            }
+            "--memory" => {
+                let range = value("--memory")?;
+                let (start, end) = range.split_once('-').ok_or("--memory must look like 200-2FF")?;
+
+                parsed.memory = Some((parse_hex(start)?, parse_hex(end)?));
+            }
+            "--no-display" => parsed.print_display = false,
+            "--screenshot" => parsed.screenshot = Some(value("--screenshot")?),
+            "--wav" => parsed.wav = Some(value("--wav")?),
+            "--waveform" => {
+                let name = value("--waveform")?;
+
+                parsed.beeper.waveform = Waveform::from_name(&name).ok_or_else(|| {
+                    format!("Unknown waveform {}, expected one of: {}", name, audio::WAVEFORM_NAMES.join(", "))
+                })?;
+            }
+            "--frequency" => {
+                parsed.beeper.frequency = value("--frequency")?
+                    .parse()
+                    .ok()
+                    .filter(|&frequency: &f32| frequency > 0.0)
+                    .ok_or("--frequency must be a positive number")?;
+            }
+            "--volume" => {
+                parsed.beeper.volume = value("--volume")?
+                    .parse()
+                    .ok()
+                    .filter(|volume| (0.0..=1.0).contains(volume))
+                    .ok_or("--volume must be a number from 0 to 1")?;
+            }
+            "--ramp" => {
+                let millis = value("--ramp")?.parse().map_err(|_| "--ramp must be a number")?;
+
+                parsed.beeper.ramp = Duration::from_millis(millis);
+            }
+            "--conformance" => parsed.conformance = true,
+            _ if arg.starts_with('-') => return Err(format!("Unknown option {}", arg)),
+            _ if rom.is_none() => rom = Some(arg),
+            _ => return Err(format!("Unexpected argument {}", arg))
+        }
+    }
+
+    if parsed.conformance {
+        return Ok(parsed);
+    }
+
+    parsed.rom = rom.ok_or("You must specify the ROM file to run.")?;
+
+    Ok(parsed)
+}
+
+fn parse_hex(text: &str) -> Result<usize, String> {
+    usize::from_str_radix(text.trim_start_matches("0x"), 16).map_err(|_| format!("{} isn't a hex address", text))
+}
@@ -0,0 +1,141 @@
+use crate::cpu::Cpu;
+use crate::headless::{self, InputScript, RunOptions};
+use crate::quirks::Quirks;
+use crate::screenshot::{Screenshot, ScreenshotDiff};
+
+use std::fmt;
+
+/// A test ROM and how to run it.
+pub struct CheckRom {
+    /// Short name used in reports.
+    pub name: &'static str,
+    /// The ROM image.
+    pub image: &'static [u8],
+    /// Frames to run before reading back the result screen.
+    pub frames: u32,
+    /// Key input, in the [`InputScript`] format.
+    pub input: &'static str,
+    /// Presets to run the ROM under, each with the PBM image of the result screen it should show.
+    pub references: &'static [(&'static str, &'static str)]
+}
+
+/// Result screen of corax89's opcode test when every opcode passes, the same under every preset.
+const CORAX_REFERENCE: &str = include_str!("../conformance/test_opcode.pbm");
+
+/// The conformance checks. ROMs and references are built in from the crate's `conformance`
+/// directory, so they run the same from an installed binary. To add a ROM, check its result screen
+/// by eye and save it with `ch8-headless ROM --preset NAME --frames N --screenshot FILE`.
+pub const CHECKS: &[CheckRom] = &[
+    CheckRom {
+        name: "corax",
+        image: include_bytes!("../conformance/test_opcode.rom"),
+        frames: 60,
+        input: "",
+        references: &[
+            ("vip", CORAX_REFERENCE),
+            ("chip48", CORAX_REFERENCE),
+            ("schip", CORAX_REFERENCE),
+            ("modern", CORAX_REFERENCE)
+        ]
+    },
+    CheckRom {
+        // draws a digit for whether each quirk is on, as listed in `conformance/quirks.txt`
+        name: "quirks",
+        image: include_bytes!("../conformance/quirks.rom"),
+        frames: 30,
+        input: "",
+        references: &[
+            ("vip", include_str!("../conformance/quirks-vip.pbm")),
+            ("chip48", include_str!("../conformance/quirks-chip48.pbm")),
+            ("schip", include_str!("../conformance/quirks-schip.pbm")),
+            ("modern", include_str!("../conformance/quirks-modern.pbm"))
+        ]
+    }
+];
+
+/// How one ROM did under one preset.
+#[derive(Debug)]
+pub enum Outcome {
+    /// The result screen matches the reference.
+    Pass,
+    /// The result screen differs from the reference.
+    Fail(ScreenshotDiff),
+    /// The ROM couldn't be run or the reference couldn't be read.
+    Error(String)
+}
+
+impl Outcome {
+    /// Checks if the outcome should fail a conformance run.
+    pub fn is_failure(&self) -> bool {
+        !matches!(self, Outcome::Pass)
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Pass => f.write_str("pass"),
+            Outcome::Fail(diff) => write!(f, "FAIL, {} pixels differ", diff.mismatched),
+            Outcome::Error(err) => write!(f, "ERROR, {}", err)
+        }
+    }
+}
+
+/// The outcome of a ROM under a preset.
+#[derive(Debug)]
+pub struct CheckResult {
+    pub rom: &'static str,
+    pub preset: &'static str,
+    pub outcome: Outcome
+}
+
+/// Runs every ROM in [`CHECKS`] under each of its presets, comparing the result screens with the
+/// references.
+pub fn run_checks() -> Vec<CheckResult> {
+    CHECKS
+        .iter()
+        .flat_map(|rom| {
+            rom.references.iter().map(move |&(preset, reference)| CheckResult {
+                rom: rom.name,
+                preset,
+                outcome: check(rom, preset, reference)
+            })
+        })
+        .collect()
+}
+
+fn check(rom: &CheckRom, preset: &str, reference: &str) -> Outcome {
+    let screenshot = match run_rom(rom, preset) {
+        Ok(screenshot) => screenshot,
+        Err(err) => return Outcome::Error(err)
+    };
+
+    match Screenshot::from_pbm(reference) {
+        Ok(expected) => match screenshot.diff(&expected) {
+            Some(diff) => Outcome::Fail(diff),
+            None => Outcome::Pass
+        },
+        Err(err) => Outcome::Error(format!("bad reference: {}", err))
+    }
+}
+
+/// Runs a ROM under a preset and captures the result screen.
+fn run_rom(rom: &CheckRom, preset: &str) -> Result<Screenshot, String> {
+    let quirks = Quirks::from_preset_name(preset).ok_or_else(|| format!("unknown preset {}", preset))?;
+    let input = InputScript::parse(rom.input).map_err(|err| err.to_string())?;
+    let mut cpu = Cpu::new();
+
+    cpu.init();
+    cpu.set_quirks(quirks);
+    cpu.seed_rng(0);
+
+    cpu.load_program(rom.image).map_err(|err| err.to_string())?;
+
+    headless::run(&mut cpu, &RunOptions {
+        frames: rom.frames,
+        input,
+        ..RunOptions::default()
+    });
+
+    Ok(Screenshot::from_display(cpu.get_display()))
+}
@@ -0,0 +1,1206 @@
+use crate::audio::{AudioSink, NullSink, Tone};
+use crate::disassembler;
+use crate::disassembler::OpCode;
+use crate::display;
+use crate::fault::{CpuFault, FaultAction, FaultKind, FaultPolicy};
+use crate::keyboard;
+use crate::quirks::Quirks;
+use crate::savestate::{SaveStateError, StateReader, StateWriter};
+use crate::trace::{TraceEntry, Tracer};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+/// Address programs are loaded at and start from unless told otherwise.
+pub const PROGRAM_START: usize = 0x200;
+
+/// Address programs for the ETI-660 are loaded at and start from.
+pub const ETI_660_PROGRAM_START: usize = 0x600;
+
+const STACK_SIZE: usize = 16;
+
+/// Most recovered faults kept until they're taken. Any more are only counted.
+const MAX_RECOVERED_FAULTS: usize = 64;
+
+/// Rate at which the delay and sound timers count down, and so the rate at which frames are run.
+pub const TIMER_FREQUENCY_HZ: u32 = 60;
+
+/// Instructions executed per frame unless overridden with [`Cpu::set_instructions_per_frame`].
+/// At 60 frames a second this is 480 instructions a second, a little under the 500 Hz CHIP-8
+/// interpreters usually aim for.
+pub const DEFAULT_INSTRUCTIONS_PER_FRAME: u32 = 8;
+
+/// Size of the standard CHIP-8 address space.
+pub const MEMORY_SIZE: usize = 0x1000;
+
+/// Size of the XO-CHIP address space.
+pub const XO_CHIP_MEMORY_SIZE: usize = 0x10000;
+
+/// Pitch register value that plays the audio pattern at 4000 bits per second.
+pub const DEFAULT_PITCH: u8 = 64;
+
+pub const FONT_SPRITES: [u8; 5 * 16] = [
+    0xF0, 0x90, 0x90, 0x90, 0xF0, // "0"
+    0x20, 0x60, 0x20, 0x20, 0x70, // "1"
+    0xF0, 0x10, 0xF0, 0x80, 0xF0, // "2"
+    0xF0, 0x10, 0xF0, 0x10, 0xF0, // "3"
+    0x90, 0x90, 0xF0, 0x10, 0x10, // "4"
+    0xF0, 0x80, 0xF0, 0x10, 0xF0, // "5"
+    0xF0, 0x80, 0xF0, 0x90, 0xF0, // "6"
+    0xF0, 0x10, 0x20, 0x40, 0x40, // "7"
+    0xF0, 0x90, 0xF0, 0x90, 0xF0, // "8"
+    0xF0, 0x90, 0xF0, 0x10, 0xF0, // "9"
+    0xF0, 0x90, 0xF0, 0x90, 0x90, // "A"
+    0xE0, 0x90, 0xE0, 0x90, 0xE0, // "B"
+    0xF0, 0x80, 0x80, 0x80, 0xF0, // "C"
+    0xE0, 0x90, 0x90, 0x90, 0xE0, // "D"
+    0xF0, 0x80, 0xF0, 0x80, 0xF0, // "E"
+    0xF0, 0x80, 0xF0, 0x80, 0x80, // "F"
+];
+
+/// SUPER-CHIP 8x10 font, loaded directly after [`FONT_SPRITES`].
+pub const BIG_FONT_SPRITES: [u8; 10 * 16] = [
+    0x3C, 0x7E, 0xE7, 0xC3, 0xC3, 0xC3, 0xC3, 0xE7, 0x7E, 0x3C, // "0"
+    0x18, 0x38, 0x58, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C, // "1"
+    0x3E, 0x7F, 0xC3, 0x06, 0x0C, 0x18, 0x30, 0x60, 0xFF, 0xFF, // "2"
+    0x3C, 0x7E, 0xC3, 0x03, 0x0E, 0x0E, 0x03, 0xC3, 0x7E, 0x3C, // "3"
+    0x06, 0x0E, 0x1E, 0x36, 0x66, 0xC6, 0xFF, 0xFF, 0x06, 0x06, // "4"
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFE, 0x03, 0xC3, 0x7E, 0x3C, // "5"
+    0x3E, 0x7C, 0xE0, 0xC0, 0xFC, 0xFE, 0xC3, 0xC3, 0x7E, 0x3C, // "6"
+    0xFF, 0xFF, 0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x60, 0x60, // "7"
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7E, 0x7E, 0xC3, 0xC3, 0x7E, 0x3C, // "8"
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7F, 0x3F, 0x03, 0x03, 0x3E, 0x7C, // "9"
+    0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // "A"
+    0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // "B"
+    0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // "C"
+    0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // "D"
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // "E"
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0, // "F"
+];
+
+const BIG_FONT_OFFSET: usize = FONT_SPRITES.len();
+
+/// End of the font sprites. Programs can't be loaded below this.
+const FONT_END: usize = BIG_FONT_OFFSET + BIG_FONT_SPRITES.len();
+
+/// Why a program couldn't be loaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadError {
+    /// The load address would put the program over the font sprites.
+    OverlapsFont { address: usize },
+    /// The program doesn't fit between the load address and the end of memory.
+    TooLarge { size: usize, address: usize, memory_size: usize }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::OverlapsFont { address } => {
+                write!(f, "load address 0x{:03X} is below 0x{:03X} and would overwrite the font", address, FONT_END)
+            }
+            LoadError::TooLarge { size, address, memory_size } => write!(
+                f,
+                "ROM is {} bytes, but only {} bytes fit from 0x{:03X} in {}K of memory",
+                size,
+                memory_size.saturating_sub(*address),
+                address,
+                memory_size / 1024
+            )
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+/// Something odd about a ROM image that doesn't stop it loading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadWarning {
+    /// The image has no bytes in it.
+    Empty,
+    /// The image is an odd number of bytes, which instructions can't be.
+    OddLength(usize)
+}
+
+impl fmt::Display for LoadWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadWarning::Empty => write!(f, "ROM is empty"),
+            LoadWarning::OddLength(size) => {
+                write!(f, "ROM is an odd number of bytes ({}), so it may be truncated or not a CHIP-8 program", size)
+            }
+        }
+    }
+}
+
+/// Checks a ROM image for anything that suggests it's damaged or not a CHIP-8 program. Such
+/// images still load, so frontends can choose whether to warn about them.
+pub fn check_program(buffer: &[u8]) -> Vec<LoadWarning> {
+    match buffer.len() {
+        0 => vec![LoadWarning::Empty],
+        size if size % 2 == 1 => vec![LoadWarning::OddLength(size)],
+        _ => Vec::new()
+    }
+}
+
+/// A CHIP-8 virtual machine: registers, memory, timers and attached peripherals.
+///
+/// The CPU does not produce sound itself. It tells an [`AudioSink`] when the sound timer starts
+/// and stops, and discards the sound until a sink is set with [`Cpu::set_audio_sink`].
+pub struct Cpu {
+    program_counter: usize,
+    index: u16,
+    stack: [u16; STACK_SIZE],
+    stack_pointer: usize,
+    memory: Vec<u8>, // 4K of memory, or 64K for XO-CHIP
+    registers: [u8; 16],
+    flags: [u8; 16], // SUPER-CHIP RPL user flags
+    audio_pattern: Option<[u8; 16]>, // XO-CHIP 1-bit audio samples
+    pitch: u8, // XO-CHIP audio playback rate
+    delay_timer: u8,
+    sound_timer: u8,
+    instructions_per_frame: u32,
+    quirks: Quirks,
+    waiting_for_display: bool,
+    exited: bool,
+    fault_policy: FaultPolicy,
+    halt_fault: Option<CpuFault>,
+    recovered_fault_kind: Option<FaultKind>,
+    recovered_faults: VecDeque<CpuFault>,
+    dropped_recovered_faults: u32,
+    tracer: Option<Tracer>,
+    audio_sink: Box<dyn AudioSink>,
+    playing_tone: Option<Tone>, // last tone reported to the audio sink
+    frames: u64, // frames run since creation, for audio timestamps
+    frame_instructions: u32, // instructions run in the current frame
+    rng: StdRng,
+    display: display::DisplayBuffer,
+    keyboard: keyboard::KeyboardInput
+}
+
+impl Cpu {
+    /// Creates a CPU with cleared memory and registers.
+    pub fn new() -> Self {
+        Cpu {
+            program_counter: PROGRAM_START,
+            index: 0,
+            stack: [0; STACK_SIZE],
+            stack_pointer: 0,
+            memory: vec![0; MEMORY_SIZE],
+            registers: [0; 16],
+            flags: [0; 16],
+            audio_pattern: None,
+            pitch: DEFAULT_PITCH,
+            delay_timer: 0,
+            sound_timer: 0,
+            instructions_per_frame: DEFAULT_INSTRUCTIONS_PER_FRAME,
+            quirks: Quirks::default(),
+            waiting_for_display: false,
+            exited: false,
+            fault_policy: FaultPolicy::default(),
+            halt_fault: None,
+            recovered_fault_kind: None,
+            recovered_faults: VecDeque::new(),
+            dropped_recovered_faults: 0,
+            tracer: None,
+            audio_sink: Box::new(NullSink),
+            playing_tone: None,
+            frames: 0,
+            frame_instructions: 0,
+            rng: StdRng::from_entropy(),
+            display: display::DisplayBuffer::new(),
+            keyboard: keyboard::KeyboardInput::new()
+        }
+    }
+
+    /// Creates a CPU with the fonts and a program loaded and the random number generator seeded
+    /// with 0, so tests get the same results on every run.
+    pub fn with_program(program: &[u8]) -> Self {
+        let mut cpu = Cpu::new();
+
+        cpu.init();
+        cpu.seed_rng(0);
+        cpu.load_program(program).expect("program doesn't fit in memory");
+
+        cpu
+    }
+
+    /// Loads the built-in font sprites into memory.
+    pub fn init(&mut self) {
+        // load font data into memory
+        self.memory[..FONT_SPRITES.len()].copy_from_slice(&FONT_SPRITES);
+        self.memory[BIG_FONT_OFFSET..BIG_FONT_OFFSET + BIG_FONT_SPRITES.len()].copy_from_slice(&BIG_FONT_SPRITES);
+    }
+
+    /// Copies a ROM image into memory at [`PROGRAM_START`].
+    pub fn load_program(&mut self, buffer: &[u8]) -> Result<(), LoadError> {
+        self.load_program_at(buffer, PROGRAM_START)
+    }
+
+    /// Copies a ROM image into memory at an address, e.g. [`ETI_660_PROGRAM_START`], and starts
+    /// execution there. Set the memory size first for programs that need a larger address space.
+    pub fn load_program_at(&mut self, buffer: &[u8], address: usize) -> Result<(), LoadError> {
+        if address < FONT_END {
+            return Err(LoadError::OverlapsFont {
+                address
+            });
+        }
+
+        if address + buffer.len() > self.memory.len() {
+            return Err(LoadError::TooLarge {
+                size: buffer.len(),
+                address,
+                memory_size: self.memory.len()
+            });
+        }
+
+        self.memory[address..address + buffer.len()].copy_from_slice(buffer);
+        self.program_counter = address;
+
+        Ok(())
+    }
+
+    /// Gets the size of the address space.
+    pub fn get_memory_size(&self) -> usize {
+        self.memory.len()
+    }
+
+    /// Resizes the address space, e.g. to [`XO_CHIP_MEMORY_SIZE`] for XO-CHIP programs.
+    /// Memory that stays in range is kept and new memory is zeroed. The size is kept between
+    /// [`MEMORY_SIZE`] and [`XO_CHIP_MEMORY_SIZE`], since the font and programs need at least 4K.
+    pub fn set_memory_size(&mut self, size: usize) {
+        self.memory.resize(size.clamp(MEMORY_SIZE, XO_CHIP_MEMORY_SIZE), 0);
+    }
+
+    /// Gets the display buffer.
+    pub fn get_display(&self) -> &display::DisplayBuffer {
+        &self.display
+    }
+
+    /// Gets the keyboard state so a frontend can feed key presses.
+    pub fn get_keyboard(&mut self) -> &mut keyboard::KeyboardInput {
+        &mut self.keyboard
+    }
+
+    /// Checks if the sound timer is active and a tone should be playing.
+    pub fn is_sound_playing(&self) -> bool {
+        self.sound_timer > 0
+    }
+
+    /// Sets where sound goes. If the sound timer is running, the new sink starts playing at once.
+    pub fn set_audio_sink(&mut self, sink: Box<dyn AudioSink>) {
+        self.audio_sink = sink;
+        self.playing_tone = None;
+        self.update_audio();
+    }
+
+    /// Gets the emulated time since the CPU was created, counting whole frames plus the
+    /// instructions run so far in the current frame.
+    pub fn get_elapsed_time(&self) -> Duration {
+        let instructions_per_frame = self.instructions_per_frame.max(1) as u128;
+        let instructions = self.frames as u128 * instructions_per_frame + self.frame_instructions as u128;
+        let nanos = instructions * 1_000_000_000 / (instructions_per_frame * TIMER_FREQUENCY_HZ as u128);
+
+        Duration::from_nanos(nanos as u64)
+    }
+
+    /// Gets the 128-bit XO-CHIP audio pattern, or `None` if the program never loaded one.
+    pub fn get_audio_pattern(&self) -> Option<[u8; 16]> {
+        self.audio_pattern
+    }
+
+    /// Gets the XO-CHIP pitch register.
+    pub fn get_pitch(&self) -> u8 {
+        self.pitch
+    }
+
+    /// Gets the number of instructions executed by each call to [`Cpu::run_frame`].
+    pub fn get_instructions_per_frame(&self) -> u32 {
+        self.instructions_per_frame
+    }
+
+    /// Sets the number of instructions executed by each call to [`Cpu::run_frame`].
+    pub fn set_instructions_per_frame(&mut self, instructions: u32) {
+        self.instructions_per_frame = instructions;
+    }
+
+    /// Reseeds the random number generator used by `CXNN` so runs are reproducible.
+    pub fn seed_rng(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    /// Gets the platform quirks the CPU emulates.
+    pub fn get_quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    /// Sets the platform quirks the CPU emulates, and resizes memory to match. Set them before
+    /// loading a program.
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+        self.set_memory_size(quirks.memory_size);
+    }
+
+    /// Gets the action taken for each kind of fault.
+    pub fn get_fault_policy(&self) -> FaultPolicy {
+        self.fault_policy
+    }
+
+    /// Sets the action taken for each kind of fault.
+    pub fn set_fault_policy(&mut self, policy: FaultPolicy) {
+        self.fault_policy = policy;
+    }
+
+    /// Checks if the program has exited with the SUPER-CHIP `00FD` instruction.
+    pub fn has_exited(&self) -> bool {
+        self.exited
+    }
+
+    /// Gets the fault that halted the CPU, if any.
+    pub fn get_halt_fault(&self) -> Option<CpuFault> {
+        self.halt_fault
+    }
+
+    /// Clears a halting fault so execution can carry on, e.g. after fixing up state in a debugger.
+    pub fn clear_halt_fault(&mut self) {
+        self.halt_fault = None;
+    }
+
+    /// Takes the oldest fault that was wrapped or ignored instead of halting the CPU. Call it
+    /// until it returns `None` to get every fault since the last time.
+    pub fn take_recovered_fault(&mut self) -> Option<CpuFault> {
+        self.recovered_faults.pop_front()
+    }
+
+    /// Takes the number of recovered faults that weren't kept because too many were waiting to
+    /// be taken.
+    pub fn take_dropped_fault_count(&mut self) -> u32 {
+        std::mem::take(&mut self.dropped_recovered_faults)
+    }
+
+    /// Gets the address of the next instruction.
+    pub fn get_program_counter(&self) -> usize {
+        self.program_counter
+    }
+
+    /// Sets the address of the next instruction.
+    pub fn set_program_counter(&mut self, addr: usize) {
+        self.program_counter = addr;
+    }
+
+    /// Gets the index register.
+    pub fn get_index(&self) -> u16 {
+        self.index
+    }
+
+    /// Sets the index register.
+    pub fn set_index(&mut self, index: u16) {
+        self.index = index;
+    }
+
+    /// Gets registers r0 - rF.
+    pub fn get_registers(&self) -> &[u8; 16] {
+        &self.registers
+    }
+
+    /// Sets a register r0 - rF.
+    pub fn set_register(&mut self, register: u8, val: u8) {
+        self.registers[register as usize & 0xF] = val;
+    }
+
+    /// Gets the return addresses currently on the stack, oldest first.
+    pub fn get_stack(&self) -> &[u16] {
+        &self.stack[..self.stack_pointer]
+    }
+
+    /// Sets how many return addresses are on the stack, up to its size of 16.
+    pub fn set_stack_pointer(&mut self, pointer: usize) {
+        self.stack_pointer = pointer.min(STACK_SIZE);
+    }
+
+    /// Gets the delay timer.
+    pub fn get_delay_timer(&self) -> u8 {
+        self.delay_timer
+    }
+
+    /// Sets the delay timer.
+    pub fn set_delay_timer(&mut self, val: u8) {
+        self.delay_timer = val;
+    }
+
+    /// Gets the sound timer.
+    pub fn get_sound_timer(&self) -> u8 {
+        self.sound_timer
+    }
+
+    /// Sets the sound timer.
+    pub fn set_sound_timer(&mut self, val: u8) {
+        self.sound_timer = val;
+        self.update_audio();
+    }
+
+    /// Gets the whole address space.
+    pub fn get_memory(&self) -> &[u8] {
+        &self.memory
+    }
+
+    /// Gets the whole address space for modification.
+    pub fn get_memory_mut(&mut self) -> &mut [u8] {
+        &mut self.memory
+    }
+
+    /// Starts writing every executed instruction to a trace, or stops tracing with `None`.
+    pub fn set_tracer(&mut self, tracer: Option<Tracer>) {
+        self.tracer = tracer;
+    }
+
+    /// Stops tracing and returns the tracer so it can be finished.
+    pub fn take_tracer(&mut self) -> Option<Tracer> {
+        self.tracer.take()
+    }
+
+    /// Serializes the complete machine state into the versioned save state format.
+    ///
+    /// Configuration such as quirks, fault policy and speed is not included, and neither is the
+    /// random number generator, so `CXNN` results can differ after loading.
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut writer = StateWriter::new();
+
+        writer.write_u32(self.program_counter as u32);
+        writer.write_u16(self.index);
+
+        for &addr in self.stack.iter() {
+            writer.write_u16(addr);
+        }
+
+        writer.write_u8(self.stack_pointer as u8);
+        writer.write_bytes(&self.registers);
+        writer.write_bytes(&self.flags);
+        writer.write_u8(self.delay_timer);
+        writer.write_u8(self.sound_timer);
+        writer.write_u32(self.memory.len() as u32);
+        writer.write_bytes(&self.memory);
+        self.display.write_state(&mut writer);
+        self.keyboard.write_state(&mut writer);
+        writer.write_bool(self.waiting_for_display);
+        writer.write_bool(self.exited);
+        writer.write_bool(self.audio_pattern.is_some());
+        writer.write_bytes(&self.audio_pattern.unwrap_or([0; 16]));
+        writer.write_u8(self.pitch);
+
+        writer.finish()
+    }
+
+    /// Restores a machine state written by [`Cpu::save_state`]. The CPU is left untouched on error.
+    ///
+    /// Loading a state also clears any halting fault.
+    pub fn load_state(&mut self, state: &[u8]) -> Result<(), SaveStateError> {
+        let mut reader = StateReader::new(state)?;
+
+        let program_counter = reader.read_u32()? as usize;
+        let index = reader.read_u16()?;
+        let mut stack = [0; STACK_SIZE];
+
+        for addr in stack.iter_mut() {
+            *addr = reader.read_u16()?;
+        }
+
+        let stack_pointer = reader.read_u8()? as usize;
+
+        if stack_pointer > STACK_SIZE {
+            return Err(SaveStateError::Invalid("stack pointer"));
+        }
+
+        let mut registers = [0; 16];
+        let mut flags = [0; 16];
+
+        registers.copy_from_slice(reader.read_bytes(16)?);
+        flags.copy_from_slice(reader.read_bytes(16)?);
+
+        let delay_timer = reader.read_u8()?;
+        let sound_timer = reader.read_u8()?;
+        let memory_size = reader.read_u32()? as usize;
+
+        if !(MEMORY_SIZE..=XO_CHIP_MEMORY_SIZE).contains(&memory_size) {
+            return Err(SaveStateError::Invalid("memory size"));
+        }
+
+        let memory = reader.read_bytes(memory_size)?.to_vec();
+        let display = display::DisplayBuffer::read_state(&mut reader)?;
+        let keyboard = keyboard::KeyboardInput::read_state(&mut reader)?;
+        let waiting_for_display = reader.read_bool()?;
+        let exited = reader.read_bool()?;
+        let has_audio_pattern = reader.read_bool()?;
+        let mut audio_pattern = [0; 16];
+
+        audio_pattern.copy_from_slice(reader.read_bytes(16)?);
+
+        let pitch = reader.read_u8()?;
+
+        self.program_counter = program_counter;
+        self.index = index;
+        self.stack = stack;
+        self.stack_pointer = stack_pointer;
+        self.registers = registers;
+        self.flags = flags;
+        self.delay_timer = delay_timer;
+        self.sound_timer = sound_timer;
+        self.memory = memory;
+        self.display = display;
+        self.keyboard = keyboard;
+        self.waiting_for_display = waiting_for_display;
+        self.exited = exited;
+        self.audio_pattern = if has_audio_pattern { Some(audio_pattern) } else { None };
+        self.pitch = pitch;
+        self.update_audio();
+        self.halt_fault = None;
+
+        Ok(())
+    }
+
+    /// Executes a single instruction without touching the timers.
+    ///
+    /// Returns the fault if the instruction halted the CPU. Once halted, the CPU stays halted
+    /// and every further step returns the same fault.
+    ///
+    /// With the display wait quirk, steps after a sprite is drawn do nothing until the next frame.
+    pub fn step(&mut self) -> Result<(), CpuFault> {
+        if let Some(fault) = self.halt_fault {
+            return Err(fault);
+        }
+
+        if self.waiting_for_display || self.exited {
+            return Ok(());
+        }
+
+        let result = self.execute();
+
+        self.frame_instructions += 1;
+        self.update_audio();
+
+        result
+    }
+
+    /// Executes a fixed number of instructions without touching the timers, stopping early on a halting fault.
+    pub fn run_cycles(&mut self, cycles: u32) -> Result<(), CpuFault> {
+        for _ in 0..cycles {
+            self.step()?;
+        }
+
+        Ok(())
+    }
+
+    /// Executes one frame: the configured number of instructions, then a single 60 Hz timer decrement.
+    ///
+    /// The timers are left untouched if the CPU halts during the frame.
+    pub fn run_frame(&mut self) -> Result<(), CpuFault> {
+        self.run_cycles(self.instructions_per_frame)?;
+        self.decrement_timers();
+
+        Ok(())
+    }
+
+    /// Counts the delay and sound timers down by one and ends any display wait, as at the start of
+    /// a new frame. [`Cpu::run_frame`] does this itself; it's only needed when driving the CPU with [`Cpu::step`].
+    pub fn decrement_timers(&mut self) {
+        self.waiting_for_display = false;
+        self.frames += 1;
+        self.frame_instructions = 0;
+
+        if self.delay_timer > 0 {
+            self.delay_timer -= 1;
+        }
+
+        if self.sound_timer > 0 {
+            self.sound_timer -= 1;
+        }
+
+        self.update_audio();
+    }
+
+    /// Tells the audio sink if the sound timer has started or stopped, or the tone has changed.
+    fn update_audio(&mut self) {
+        let tone = if self.is_sound_playing() {
+            Some(Tone::new(self.audio_pattern, self.pitch))
+        } else {
+            None
+        };
+
+        if tone == self.playing_tone {
+            return;
+        }
+
+        let time = self.get_elapsed_time();
+
+        match tone {
+            Some(tone) => self.audio_sink.start(time, tone),
+            None => self.audio_sink.stop(time)
+        }
+
+        self.playing_tone = tone;
+    }
+
+    fn execute(&mut self) -> Result<(), CpuFault> {
+        let addr = self.program_counter;
+
+        if addr + 1 >= self.memory.len() {
+            match self.fault_policy.program_counter_out_of_bounds {
+                FaultAction::Wrap => {
+                    // an instruction straddling the end of memory wraps its second byte too
+                    self.program_counter = addr % self.memory.len();
+                    self.record_recovered_fault(CpuFault {
+                        address: addr,
+                        word: 0,
+                        kind: FaultKind::ProgramCounterOutOfBounds
+                    });
+                }
+                FaultAction::Halt | FaultAction::Ignore => {
+                    return Err(self.halt(addr, 0, FaultKind::ProgramCounterOutOfBounds));
+                }
+            }
+        }
+
+        let addr = self.program_counter;
+        let word = u16::from_be_bytes([
+            self.memory[addr],
+            self.memory[(addr + 1) % self.memory.len()]
+        ]);
+
+        let opcode = disassembler::disassemble_word(word);
+
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.trace(&TraceEntry {
+                address: addr,
+                word,
+                opcode: opcode.as_ref(),
+                registers: &self.registers,
+                index: self.index,
+                delay_timer: self.delay_timer,
+                sound_timer: self.sound_timer
+            });
+        }
+
+        self.advance();
+
+        let result = match opcode {
+            Some(opcode) => self.execute_opcode(opcode),
+            None => self.resolve_fault(FaultKind::UnknownOpcode).map(|_| ())
+        };
+
+        if let Err(kind) = result {
+            return Err(self.halt(addr, word, kind));
+        }
+
+        if let Some(kind) = self.recovered_fault_kind.take() {
+            self.record_recovered_fault(CpuFault {
+                address: addr,
+                word,
+                kind
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Queues a recovered fault to be reported, or counts it if the queue is full.
+    fn record_recovered_fault(&mut self, fault: CpuFault) {
+        if self.recovered_faults.len() < MAX_RECOVERED_FAULTS {
+            self.recovered_faults.push_back(fault);
+        } else {
+            self.dropped_recovered_faults = self.dropped_recovered_faults.saturating_add(1);
+        }
+    }
+
+    /// Halts the CPU on the instruction at `addr` and records the fault.
+    fn halt(&mut self, addr: usize, word: u16, kind: FaultKind) -> CpuFault {
+        let fault = CpuFault {
+            address: addr,
+            word,
+            kind
+        };
+
+        self.program_counter = addr;
+        self.halt_fault = Some(fault);
+
+        fault
+    }
+
+    /// Looks up the fault policy, returning the fault if it should halt the CPU.
+    /// Otherwise the fault is remembered so it can be reported once the instruction completes.
+    fn resolve_fault(&mut self, kind: FaultKind) -> Result<FaultAction, FaultKind> {
+        match self.fault_policy.action_for(kind) {
+            FaultAction::Halt => Err(kind),
+            action => {
+                self.recovered_fault_kind = Some(kind);
+
+                Ok(action)
+            }
+        }
+    }
+
+    /// Checks that `len` bytes starting at `start` fit in memory.
+    /// Returns false if the access should be skipped; otherwise addresses should be wrapped with the memory size.
+    fn check_memory_access(&mut self, start: usize, len: usize) -> Result<bool, FaultKind> {
+        if start + len <= self.memory.len() {
+            return Ok(true);
+        }
+
+        Ok(self.resolve_fault(FaultKind::MemoryOutOfBounds)? == FaultAction::Wrap)
+    }
+
+    /// Checks a key read from a register is from 0 to F, giving the key to use.
+    fn check_key(&mut self, key: u8) -> Result<u8, FaultKind> {
+        if key > 0xF {
+            self.resolve_fault(FaultKind::InvalidKey)?;
+        }
+
+        Ok(key & 0xF)
+    }
+
+    /// advance to the next instruction
+    fn advance(&mut self) {
+        self.program_counter += 2;
+    }
+
+    /// skip the next instruction, including both words of an XO-CHIP `F000 NNNN`
+    fn skip(&mut self) {
+        let size = self.memory.len();
+        let addr = self.program_counter;
+
+        if self.memory[addr % size] == 0xF0 && self.memory[(addr + 1) % size] == 0x00 {
+            self.advance();
+        }
+
+        self.advance();
+    }
+
+    fn execute_opcode(&mut self, opcode: OpCode) -> Result<(), FaultKind> {
+        match opcode {
+            OpCode::MachineCall(_) => {
+                // machine code routines can't run here, so this is a no-op when ignored
+                self.resolve_fault(FaultKind::MachineCodeCall)?;
+            }
+            OpCode::ScrollDown(n) => {
+                // scrolls the display down by N pixels
+                self.display.scroll_down(n);
+            }
+            OpCode::ScrollUp(n) => {
+                // scrolls the display up by N pixels
+                self.display.scroll_up(n);
+            }
+            OpCode::ClearDisplay => {
+                // clears entire display
+                self.display.clear();
+            }
+            OpCode::ScrollRight => {
+                // scrolls the display right by 4 pixels
+                self.display.scroll_right(4);
+            }
+            OpCode::ScrollLeft => {
+                // scrolls the display left by 4 pixels
+                self.display.scroll_left(4);
+            }
+            OpCode::Exit => {
+                // stops the interpreter
+                self.exited = true;
+            }
+            OpCode::LowRes => {
+                // switches to 64x32 resolution
+                self.display.set_hires(false);
+            }
+            OpCode::HighRes => {
+                // switches to 128x64 resolution
+                self.display.set_hires(true);
+            }
+            OpCode::Return => {
+                // pops a return address from the stack, then jumps to it
+                if self.stack_pointer == 0 {
+                    match self.resolve_fault(FaultKind::StackUnderflow)? {
+                        FaultAction::Wrap => self.stack_pointer = STACK_SIZE,
+                        _ => return Ok(())
+                    }
+                }
+
+                self.stack_pointer -= 1;
+                self.program_counter = self.stack[self.stack_pointer] as usize;
+            }
+            OpCode::Jump(addr) => {
+                // sets program counter without pushing return address
+                self.program_counter = addr as usize;
+            }
+            OpCode::Call(addr) => {
+                // pushes return address to the stack, then jumps to address
+                if self.stack_pointer == STACK_SIZE {
+                    match self.resolve_fault(FaultKind::StackOverflow)? {
+                        FaultAction::Wrap => self.stack_pointer = 0,
+                        _ => return Ok(())
+                    }
+                }
+
+                self.stack[self.stack_pointer] = self.program_counter as u16;
+                self.stack_pointer += 1;
+                self.program_counter = addr as usize;
+            }
+            OpCode::SkipEqVal(x, val) => {
+                // skips the next instruction if rX is equal to a value
+                if self.registers[x as usize] == val {
+                    self.skip();
+                }
+            }
+            OpCode::SkipNotEqVal(x, val) => {
+                // skips the next instruction if rX is not equal to a value
+                if self.registers[x as usize] != val {
+                    self.skip();
+                }
+            }
+            OpCode::SkipEq(x, y) => {
+                // skips the next instruction if rX is equal to rY
+                if self.registers[x as usize] == self.registers[y as usize] {
+                    self.skip();
+                }
+            }
+            OpCode::SaveRange(x, y) => {
+                // stores registers rX - rY into memory at current index, in either order
+                let addr = self.index as usize;
+                let registers = register_range(x, y);
+
+                if !self.check_memory_access(addr, registers.len())? {
+                    return Ok(());
+                }
+
+                let size = self.memory.len();
+
+                for (i, register) in registers.into_iter().enumerate() {
+                    self.memory[(addr + i) % size] = self.registers[register];
+                }
+            }
+            OpCode::LoadRange(x, y) => {
+                // reads memory at current index into registers rX - rY, in either order
+                let addr = self.index as usize;
+                let registers = register_range(x, y);
+
+                if !self.check_memory_access(addr, registers.len())? {
+                    return Ok(());
+                }
+
+                for (i, register) in registers.into_iter().enumerate() {
+                    self.registers[register] = self.memory[(addr + i) % self.memory.len()];
+                }
+            }
+            OpCode::SetVal(x, val) => {
+                // set a value into a register
+                self.registers[x as usize] = val;
+            }
+            OpCode::AddVal(x, val) => {
+                // adds a value to a register w/o carry flag
+                self.registers[x as usize] = self.registers[x as usize].wrapping_add(val);
+            }
+            OpCode::Copy(x, y) => {
+                // copies a value from rY to rX
+                self.registers[x as usize] = self.registers[y as usize];
+            }
+            OpCode::Or(x, y) => {
+                // sets rX to bitwise OR of rX and rY
+                self.registers[x as usize] |= self.registers[y as usize];
+                self.reset_flag_after_logic();
+            }
+            OpCode::And(x, y) => {
+                // sets rX to bitwise AND of rX and rY
+                self.registers[x as usize] &= self.registers[y as usize];
+                self.reset_flag_after_logic();
+            }
+            OpCode::Xor(x, y) => {
+                // sets rX to bitwise XOR of rX and rY
+                self.registers[x as usize] ^= self.registers[y as usize];
+                self.reset_flag_after_logic();
+            }
+            OpCode::Add(x, y) => {
+                // adds rY to rX and sets flag to 1 if there is a carry
+                let result = self.registers[x as usize] as u16 + self.registers[y as usize] as u16;
+
+                self.registers[x as usize] = (result & 0xFF) as u8;
+                self.registers[0xF] = (result > 0xFF) as u8;
+            }
+            OpCode::Subtract(x, y) => {
+                // subtracts rY from rX and sets flag to 0 if there is a borrow
+                let result = self.registers[x as usize] as i16 - self.registers[y as usize] as i16;
+
+                self.registers[x as usize] = (result % 0x100i16) as u8;
+                self.registers[0xF] = (result >= 0) as u8;
+            }
+            OpCode::ShiftRight(x, y) => {
+                // stores LSB as flag, then shifts rX (or rY with the shift quirk) to the right once into rX
+                let val = self.shift_source(x, y);
+
+                self.registers[x as usize] = val >> 1;
+                self.registers[0xF] = val & 1;
+            }
+            OpCode::Difference(x, y) => {
+                // sets rX to rY minus rX and sets flag to 0 if there is a borrow
+                let result = self.registers[y as usize] as i16 - self.registers[x as usize] as i16;
+
+                self.registers[x as usize] = (result % 0x100i16) as u8;
+                self.registers[0xF] = (result >= 0) as u8;
+            }
+            OpCode::ShiftLeft(x, y) => {
+                // stores MSB as flag, then shifts rX (or rY with the shift quirk) to the left once into rX
+                let val = self.shift_source(x, y);
+
+                self.registers[x as usize] = val << 1;
+                self.registers[0xF] = ((val & 0b1000_0000) > 0) as u8;
+            }
+            OpCode::SkipNotEq(x, y) => {
+                // skips the next instruction if rX is not equal to rY
+                if self.registers[x as usize] != self.registers[y as usize] {
+                    self.skip();
+                }
+            }
+            OpCode::SetIndex(addr) => {
+                // sets index register
+                self.index = addr;
+            }
+            OpCode::JumpOffset(addr) => {
+                // jumps to address with an offset of r0, or of rX where X is the high nibble with the jump quirk
+                let offset_register = if self.quirks.jump_uses_vx {
+                    (addr >> 8) as usize
+                } else {
+                    0
+                };
+
+                self.program_counter = (addr + (self.registers[offset_register] as u16)) as usize;
+            }
+            OpCode::Rand(x, val) => {
+                // set rX to result of bitwise AND of value and random 8-bit integer
+                let rand: u8 = self.rng.gen();
+
+                self.registers[x as usize] = rand & val;
+            }
+            OpCode::DrawSprite(x, y, num_bytes) => {
+                // draws a sprite from memory onto the display, and sets collision flag
+                // a height of 0 draws a 16x16 sprite made of 32 bytes, and each selected plane takes its own sprite
+                let start = self.index as usize;
+                let sprite_len = if num_bytes == 0 { 32 } else { num_bytes as usize };
+                let len = sprite_len * self.display.get_selected_plane_count();
+
+                if !self.check_memory_access(start, len)? {
+                    return Ok(());
+                }
+
+                let bytes: Vec<u8> = (start..start + len)
+                    .map(|addr| self.memory[addr % self.memory.len()])
+                    .collect();
+                let (x, y) = (self.registers[x as usize], self.registers[y as usize]);
+                let collision = if num_bytes == 0 {
+                    self.display.draw_large_sprite(x, y, &bytes, self.quirks.clip_sprites)
+                } else {
+                    self.display.draw_sprite(x, y, &bytes, self.quirks.clip_sprites)
+                };
+
+                self.registers[0xF] = collision as u8;
+                self.waiting_for_display = self.quirks.display_wait;
+            }
+            OpCode::SkipKeyPressed(x) => {
+                // skips next instruction if key at rX is pressed
+                let key = self.check_key(self.registers[x as usize])?;
+
+                if self.keyboard.is_key_pressed(key) {
+                    self.skip();
+                }
+            }
+            OpCode::SkipKeyNotPressed(x) => {
+                // skips next instruction if key at rX is not pressed
+                let key = self.check_key(self.registers[x as usize])?;
+
+                if !self.keyboard.is_key_pressed(key) {
+                    self.skip();
+                }
+            }
+            OpCode::GetDelayTimer(x) => {
+                // sets rX to value of delay timer
+                self.registers[x as usize] = self.delay_timer;
+            }
+            OpCode::GetKeyPress(x) => {
+                // blocks until any key is pressed, then stores that key in rX
+
+                // if we're not already tracking a key, start
+                if !self.keyboard.is_tracking_next_key_release() {
+                    self.keyboard.track_next_key_release(true);
+                }
+
+                // see if a key has been released yet
+                if let Some(key) = self.keyboard.get_last_released_key() {
+                    self.keyboard.track_next_key_release(false);
+                    self.registers[x as usize] = key;
+                } else {
+                    // no key released, so run this instruction again
+                    self.program_counter -= 2;
+                }
+            }
+            OpCode::SetIndexLong => {
+                // sets index register to the 16-bit address in the next word
+                let addr = self.program_counter;
+
+                if !self.check_memory_access(addr, 2)? {
+                    return Ok(());
+                }
+
+                self.index = u16::from_be_bytes([
+                    self.memory[addr % self.memory.len()],
+                    self.memory[(addr + 1) % self.memory.len()]
+                ]);
+                self.advance();
+            }
+            OpCode::SelectPlanes(n) => {
+                // selects the display planes that drawing, scrolling and clearing affect
+                self.display.select_planes(n);
+            }
+            OpCode::LoadAudioPattern => {
+                // loads 16 bytes of audio samples from memory at current index
+                let addr = self.index as usize;
+
+                if !self.check_memory_access(addr, 16)? {
+                    return Ok(());
+                }
+
+                let mut pattern = [0; 16];
+
+                for (i, sample) in pattern.iter_mut().enumerate() {
+                    *sample = self.memory[(addr + i) % self.memory.len()];
+                }
+
+                self.audio_pattern = Some(pattern);
+            }
+            OpCode::SetDelayTimer(x) => {
+                // sets delay timer to value of rX
+                self.delay_timer = self.registers[x as usize];
+            }
+            OpCode::SetSoundTimer(x) => {
+                // sets sound timer to value of rX
+                self.sound_timer = self.registers[x as usize];
+            }
+            OpCode::AddIndex(x) => {
+                // adds rX to index register
+                let (result, overflow) = self.index.overflowing_add(self.registers[x as usize] as u16);
+
+                if overflow && self.resolve_fault(FaultKind::IndexOverflow)? == FaultAction::Ignore {
+                    return Ok(());
+                }
+
+                self.index = result;
+            }
+            OpCode::SetIndexCharacter(x) => {
+                // sets memory index to sprite of the character that is in a register
+                self.index = self.registers[x as usize] as u16 * 5;
+            }
+            OpCode::SetIndexBigCharacter(x) => {
+                // sets memory index to the large sprite of the character that is in a register
+                self.index = (BIG_FONT_OFFSET + (self.registers[x as usize] & 0xF) as usize * 10) as u16;
+            }
+            OpCode::SetPitch(x) => {
+                // sets the audio pattern playback rate to value of rX
+                self.pitch = self.registers[x as usize];
+            }
+            OpCode::StoreBcd(x) => {
+                // store binary-coded decimal in memory
+                let val = self.registers[x as usize];
+                let addr = self.index as usize;
+
+                if !self.check_memory_access(addr, 3)? {
+                    return Ok(());
+                }
+
+                let size = self.memory.len();
+
+                self.memory[addr % size] = val / 100;
+                self.memory[(addr + 1) % size] = (val / 10) % 10;
+                self.memory[(addr + 2) % size] = val % 10;
+            }
+            OpCode::RegDump(x) => {
+                // stores registers r0 - rX into memory at current index
+                let addr = self.index as usize;
+
+                if !self.check_memory_access(addr, x as usize + 1)? {
+                    return Ok(());
+                }
+
+                let size = self.memory.len();
+
+                for i in 0..=x as usize {
+                    self.memory[(addr + i) % size] = self.registers[i];
+                }
+
+                self.increment_index_after_load_store(x);
+            }
+            OpCode::RegLoad(x) => {
+                // reads memory at current index and stores bytes into registers r0 - rX
+                let addr = self.index as usize;
+
+                if !self.check_memory_access(addr, x as usize + 1)? {
+                    return Ok(());
+                }
+
+                for i in 0..=x as usize {
+                    self.registers[i] = self.memory[(addr + i) % self.memory.len()];
+                }
+
+                self.increment_index_after_load_store(x);
+            }
+            OpCode::StoreFlags(x) => {
+                // stores registers r0 - rX into the user flags
+                let count = x as usize + 1;
+
+                self.flags[..count].copy_from_slice(&self.registers[..count]);
+            }
+            OpCode::LoadFlags(x) => {
+                // reads the user flags into registers r0 - rX
+                let count = x as usize + 1;
+
+                self.registers[..count].copy_from_slice(&self.flags[..count]);
+            }
+        };
+
+        Ok(())
+    }
+
+    /// Gets the value to shift for `8XY6`/`8XYE` depending on the shift quirk.
+    fn shift_source(&self, x: u8, y: u8) -> u8 {
+        if self.quirks.shift_uses_vy {
+            self.registers[y as usize]
+        } else {
+            self.registers[x as usize]
+        }
+    }
+
+    /// Resets the flag register after a logic instruction if the VF reset quirk is enabled.
+    fn reset_flag_after_logic(&mut self) {
+        if self.quirks.logic_resets_vf {
+            self.registers[0xF] = 0;
+        }
+    }
+
+    /// Moves the index register past rX after `FX55`/`FX65` if the load/store quirk is enabled.
+    fn increment_index_after_load_store(&mut self, x: u8) {
+        if self.quirks.load_store_increments_index {
+            self.index = self.index.wrapping_add(x as u16 + 1);
+        }
+    }
+}
+
+impl Default for Cpu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Register indices from X to Y inclusive, counting down if Y is below X.
+fn register_range(x: u8, y: u8) -> Vec<usize> {
+    if x <= y {
+        (x as usize..=y as usize).collect()
+    } else {
+        (y as usize..=x as usize).rev().collect()
+    }
+}
@@ -0,0 +1,117 @@
+//! Known ROMs, identified by their SHA-1 hash, with the settings they're meant to run with. The
+//! entries are taken from the [CHIP-8 database](https://github.com/chip-8/chip-8-database).
+
+use std::fmt;
+
+/// The platform a ROM was written for, with the CHIP-8 database's id for each.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    /// The original interpreter on the COSMAC VIP (`originalChip8`).
+    Chip8,
+    /// CHIP-8 as most interpreters since the DOS ones run it, without the VIP's quirks
+    /// (`modernChip8`).
+    ModernChip8,
+    /// CHIP-48 on the HP-48 graphing calculators (`chip48`).
+    Chip48,
+    /// `superchip`
+    SuperChip,
+    /// `xochip`
+    XoChip
+}
+
+impl Platform {
+    /// Gets the quirks preset for the platform, one of [`crate::quirks::PRESET_NAMES`]. Modern
+    /// CHIP-8 has none, as ROMs for it run as they always have without a preset.
+    pub fn get_preset_name(self) -> Option<&'static str> {
+        match self {
+            Platform::Chip8 => Some("vip"),
+            Platform::ModernChip8 => None,
+            Platform::Chip48 => Some("chip48"),
+            Platform::SuperChip => Some("schip"),
+            Platform::XoChip => Some("modern")
+        }
+    }
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Platform::Chip8 | Platform::ModernChip8 => "CHIP-8",
+            Platform::Chip48 => "CHIP-48",
+            Platform::SuperChip => "SUPER-CHIP",
+            Platform::XoChip => "XO-CHIP"
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// What's known about a ROM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RomInfo {
+    /// Lowercase hex SHA-1 hash of the ROM image.
+    pub sha1: &'static str,
+    pub title: &'static str,
+    pub author: Option<&'static str>,
+    pub platform: Platform,
+    /// Instructions per frame the ROM plays best at, if it's not the usual speed.
+    pub instructions_per_frame: Option<u32>,
+    /// What the ROM's keys do, e.g. `(0x5, "fire")`.
+    pub keys: &'static [(u8, &'static str)],
+    /// Background and foreground colours as `0xRRGGBB`, if the ROM was made for particular colours.
+    pub colors: Option<[u32; 2]>
+}
+
+impl fmt::Display for RomInfo {
+    /// Formats the title, author and platform, e.g. `Pong by Paul Vervalin (CHIP-8)`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.title)?;
+
+        if let Some(author) = self.author {
+            write!(f, " by {}", author)?;
+        }
+
+        write!(f, " ({})", self.platform)
+    }
+}
+
+/// The ROMs in the database.
+pub const ROMS: &[RomInfo] = &[
+    RomInfo {
+        sha1: "b232ef880bd6060fb45fa6effed7edf0ae95670e",
+        title: "Pong",
+        author: Some("Paul Vervalin"),
+        platform: Platform::ModernChip8,
+        instructions_per_frame: None,
+        keys: &[(0x1, "up"), (0x4, "down")],
+        colors: None
+    },
+    RomInfo {
+        sha1: "5c28a5f85289c9d859f95fd5eadbdcb1c30bb08b",
+        title: "Space Invaders",
+        author: Some("David Winter"),
+        platform: Platform::ModernChip8,
+        instructions_per_frame: None,
+        keys: &[(0x4, "left"), (0x6, "right"), (0x5, "fire / start")],
+        colors: None
+    },
+    RomInfo {
+        sha1: "f1cfcffe1937ed6dd6eeed1a7f85dfc777bda700",
+        title: "CHIP-8 Test ROM",
+        author: Some("corax89"),
+        platform: Platform::Chip8,
+        instructions_per_frame: None,
+        keys: &[],
+        colors: None
+    }
+];
+
+/// Looks up a ROM by its SHA-1 hash in hex.
+pub fn find(sha1: &str) -> Option<&'static RomInfo> {
+    ROMS.iter().find(|rom| rom.sha1.eq_ignore_ascii_case(sha1))
+}
+
+/// Hashes a ROM image and looks it up.
+pub fn identify(rom: &[u8]) -> Option<&'static RomInfo> {
+    find(&crate::sha1::hex_digest(rom))
+}
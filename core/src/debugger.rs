@@ -0,0 +1,547 @@
+use crate::cpu::Cpu;
+use crate::disassembler::{self, OpCode};
+use crate::fault::CpuFault;
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+/// Upper bound on instructions executed by step over and step out, so a call that never
+/// returns can't hang the caller.
+pub const DEFAULT_STEP_LIMIT: u32 = 1_000_000;
+
+/// A register that conditions can watch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Register {
+    V(u8),
+    Index,
+    DelayTimer,
+    SoundTimer
+}
+
+impl Register {
+    /// Parses `v0` - `vf`, `i`, `dt` or `st`.
+    pub fn parse(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+
+        match name.as_str() {
+            "i" => Some(Register::Index),
+            "dt" => Some(Register::DelayTimer),
+            "st" => Some(Register::SoundTimer),
+            _ => {
+                let digit = name.strip_prefix('v')?;
+
+                u8::from_str_radix(digit, 16)
+                    .ok()
+                    .filter(|&x| x <= 0xF && digit.len() == 1)
+                    .map(Register::V)
+            }
+        }
+    }
+
+    /// Reads the register from a CPU.
+    pub fn read(self, cpu: &Cpu) -> u16 {
+        match self {
+            Register::V(x) => cpu.get_registers()[x as usize] as u16,
+            Register::Index => cpu.get_index(),
+            Register::DelayTimer => cpu.get_delay_timer() as u16,
+            Register::SoundTimer => cpu.get_sound_timer() as u16
+        }
+    }
+
+    /// Writes the register on a CPU, truncating to 8 bits for everything except the index.
+    pub fn write(self, cpu: &mut Cpu, val: u16) {
+        match self {
+            Register::V(x) => cpu.set_register(x, val as u8),
+            Register::Index => cpu.set_index(val),
+            Register::DelayTimer => cpu.set_delay_timer(val as u8),
+            Register::SoundTimer => cpu.set_sound_timer(val as u8)
+        }
+    }
+}
+
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Register::V(x) => write!(f, "V{:X}", x),
+            Register::Index => f.write_str("I"),
+            Register::DelayTimer => f.write_str("DT"),
+            Register::SoundTimer => f.write_str("ST")
+        }
+    }
+}
+
+/// How a condition compares a register against a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual
+}
+
+impl Comparison {
+    /// Parses `==`, `!=`, `<`, `<=`, `>` or `>=`.
+    pub fn parse(op: &str) -> Option<Self> {
+        match op {
+            "==" => Some(Comparison::Equal),
+            "!=" => Some(Comparison::NotEqual),
+            "<" => Some(Comparison::Less),
+            "<=" => Some(Comparison::LessOrEqual),
+            ">" => Some(Comparison::Greater),
+            ">=" => Some(Comparison::GreaterOrEqual),
+            _ => None
+        }
+    }
+
+    fn compare(self, a: u16, b: u16) -> bool {
+        match self {
+            Comparison::Equal => a == b,
+            Comparison::NotEqual => a != b,
+            Comparison::Less => a < b,
+            Comparison::LessOrEqual => a <= b,
+            Comparison::Greater => a > b,
+            Comparison::GreaterOrEqual => a >= b
+        }
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            Comparison::Equal => "==",
+            Comparison::NotEqual => "!=",
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">="
+        };
+
+        f.write_str(op)
+    }
+}
+
+/// Breaks when a register comparison becomes true.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Condition {
+    pub register: Register,
+    pub comparison: Comparison,
+    pub value: u16
+}
+
+impl Condition {
+    /// Parses a condition like `v3 == 5` or `i >= 0x300`. Values are hex, with or without `0x`.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts = text.split_whitespace();
+        let register = Register::parse(parts.next()?)?;
+        let comparison = Comparison::parse(parts.next()?)?;
+        let value = parts.next()?;
+        let value = u16::from_str_radix(value.trim_start_matches("0x"), 16).ok()?;
+
+        if parts.next().is_some() {
+            return None;
+        }
+
+        Some(Condition {
+            register,
+            comparison,
+            value
+        })
+    }
+
+    /// Checks the condition against a CPU.
+    pub fn is_met(&self, cpu: &Cpu) -> bool {
+        self.comparison.compare(self.register.read(cpu), self.value)
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} 0x{:X}", self.register, self.comparison, self.value)
+    }
+}
+
+/// The kinds of memory access a watchpoint breaks on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchKind {
+    Read,
+    Write,
+    ReadWrite
+}
+
+impl WatchKind {
+    fn matches(self, access: Access) -> bool {
+        match self {
+            WatchKind::Read => access == Access::Read,
+            WatchKind::Write => access == Access::Write,
+            WatchKind::ReadWrite => true
+        }
+    }
+}
+
+/// Breaks before an instruction reads or writes a range of memory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub range: RangeInclusive<usize>,
+    pub kind: WatchKind
+}
+
+/// A memory access made by an instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write
+}
+
+/// Why the debugger stopped execution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StopReason {
+    /// A single step, step over or step out finished.
+    Step,
+    /// The next instruction is on a breakpoint.
+    Breakpoint(usize),
+    /// The next instruction accesses watched memory.
+    Watchpoint { address: usize, access: Access },
+    /// A register condition became true.
+    Condition(Condition),
+    /// The last instruction drew a sprite that collided.
+    Collision,
+    /// The next instruction is one of the opcode kinds being watched.
+    Opcode(String),
+    /// The CPU halted on a fault.
+    Fault(CpuFault),
+    /// The program exited.
+    Exited
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StopReason::Step => f.write_str("step"),
+            StopReason::Breakpoint(addr) => write!(f, "breakpoint at 0x{:03X}", addr),
+            StopReason::Watchpoint { address, access } => write!(f, "{:?} watchpoint at 0x{:03X}", access, address),
+            StopReason::Condition(condition) => write!(f, "condition {}", condition),
+            StopReason::Collision => f.write_str("sprite collision"),
+            StopReason::Opcode(kind) => write!(f, "{} opcode", kind),
+            StopReason::Fault(fault) => write!(f, "fault: {}", fault),
+            StopReason::Exited => f.write_str("program exited")
+        }
+    }
+}
+
+/// Breakpoints, watchpoints and stepping on top of a [`Cpu`].
+///
+/// Breakpoints, watchpoints and opcode breaks stop before the instruction runs, so it can be
+/// inspected first. Conditions and collisions stop after the instruction that caused them.
+/// Resuming from a break runs the instruction it stopped on rather than breaking on it again.
+#[derive(Default)]
+pub struct Debugger {
+    breakpoints: BTreeSet<usize>,
+    watchpoints: Vec<Watchpoint>,
+    conditions: Vec<Condition>,
+    break_opcodes: Vec<String>,
+    break_on_collision: bool,
+    /// Address of the instruction a break stopped before, so resuming runs it instead of breaking again.
+    stopped_at: Option<usize>
+}
+
+impl Debugger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a breakpoint, returning false if there already was one at the address.
+    pub fn add_breakpoint(&mut self, addr: usize) -> bool {
+        self.breakpoints.insert(addr)
+    }
+
+    /// Removes a breakpoint, returning false if there wasn't one at the address.
+    pub fn remove_breakpoint(&mut self, addr: usize) -> bool {
+        self.breakpoints.remove(&addr)
+    }
+
+    /// Gets the breakpoint addresses in order.
+    pub fn get_breakpoints(&self) -> impl Iterator<Item = usize> + '_ {
+        self.breakpoints.iter().copied()
+    }
+
+    /// Checks if there's a breakpoint at an address.
+    pub fn has_breakpoint(&self, addr: usize) -> bool {
+        self.breakpoints.contains(&addr)
+    }
+
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+        self.watchpoints.push(watchpoint);
+    }
+
+    /// Removes a watchpoint, returning false if there wasn't one exactly like it.
+    pub fn remove_watchpoint(&mut self, watchpoint: &Watchpoint) -> bool {
+        match self.watchpoints.iter().position(|w| w == watchpoint) {
+            Some(index) => {
+                self.watchpoints.remove(index);
+                true
+            }
+            None => false
+        }
+    }
+
+    /// Removes every watchpoint covering an address, returning how many were removed.
+    pub fn remove_watchpoints_at(&mut self, addr: usize) -> usize {
+        let before = self.watchpoints.len();
+
+        self.watchpoints.retain(|watchpoint| !watchpoint.range.contains(&addr));
+
+        before - self.watchpoints.len()
+    }
+
+    pub fn get_watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
+
+    pub fn add_condition(&mut self, condition: Condition) {
+        self.conditions.push(condition);
+    }
+
+    /// Removes a condition by its position in [`Debugger::get_conditions`].
+    pub fn remove_condition(&mut self, index: usize) -> Option<Condition> {
+        if index < self.conditions.len() {
+            Some(self.conditions.remove(index))
+        } else {
+            None
+        }
+    }
+
+    pub fn get_conditions(&self) -> &[Condition] {
+        &self.conditions
+    }
+
+    /// Breaks before any instruction of an opcode kind, named like the [`OpCode`] variants.
+    pub fn add_opcode_break(&mut self, kind: &str) {
+        if !self.break_opcodes.iter().any(|k| k == kind) {
+            self.break_opcodes.push(kind.to_string());
+        }
+    }
+
+    pub fn remove_opcode_break(&mut self, kind: &str) -> bool {
+        let before = self.break_opcodes.len();
+
+        self.break_opcodes.retain(|k| k != kind);
+
+        before != self.break_opcodes.len()
+    }
+
+    pub fn get_opcode_breaks(&self) -> &[String] {
+        &self.break_opcodes
+    }
+
+    /// Sets whether to break after a sprite draw that collides.
+    pub fn set_break_on_collision(&mut self, enabled: bool) {
+        self.break_on_collision = enabled;
+    }
+
+    pub fn is_breaking_on_collision(&self) -> bool {
+        self.break_on_collision
+    }
+
+    /// Executes one instruction.
+    pub fn step(&mut self, cpu: &mut Cpu) -> StopReason {
+        self.execute(cpu).unwrap_or(StopReason::Step)
+    }
+
+    /// Executes one instruction, running a whole subroutine if it is a call.
+    pub fn step_over(&mut self, cpu: &mut Cpu, limit: u32) -> StopReason {
+        let addr = cpu.get_program_counter();
+        let depth = cpu.get_stack().len();
+
+        match decode(cpu, addr) {
+            Some(OpCode::Call(_)) => self.run_until(cpu, limit, |cpu| {
+                cpu.get_program_counter() == addr + 2 && cpu.get_stack().len() == depth
+            }),
+            _ => self.step(cpu)
+        }
+    }
+
+    /// Runs until the current subroutine returns.
+    pub fn step_out(&mut self, cpu: &mut Cpu, limit: u32) -> StopReason {
+        let depth = cpu.get_stack().len();
+
+        if depth == 0 {
+            return self.step(cpu);
+        }
+
+        self.run_until(cpu, limit, |cpu| cpu.get_stack().len() < depth)
+    }
+
+    /// Runs up to `cycles` instructions, returning why it stopped early, if it did.
+    pub fn run(&mut self, cpu: &mut Cpu, cycles: u32) -> Option<StopReason> {
+        for _ in 0..cycles {
+            if let Some(reason) = self.check(cpu).or_else(|| self.execute(cpu)) {
+                return Some(reason);
+            }
+        }
+
+        None
+    }
+
+    /// Runs one frame of instructions and then counts the timers down, unless something breaks first.
+    pub fn run_frame(&mut self, cpu: &mut Cpu) -> Option<StopReason> {
+        if let Some(reason) = self.run(cpu, cpu.get_instructions_per_frame()) {
+            return Some(reason);
+        }
+
+        cpu.decrement_timers();
+
+        None
+    }
+
+    /// Steps until `done` is true or something breaks.
+    fn run_until<F: Fn(&Cpu) -> bool>(&mut self, cpu: &mut Cpu, limit: u32, done: F) -> StopReason {
+        for i in 0..limit {
+            if i > 0 {
+                if let Some(reason) = self.check(cpu) {
+                    return reason;
+                }
+            }
+
+            if let Some(reason) = self.execute(cpu) {
+                return reason;
+            }
+
+            if done(cpu) {
+                break;
+            }
+        }
+
+        StopReason::Step
+    }
+
+    /// Checks the next instruction, unless execution already stopped on it and is now resuming.
+    fn check(&mut self, cpu: &Cpu) -> Option<StopReason> {
+        let addr = cpu.get_program_counter();
+
+        if self.stopped_at == Some(addr) {
+            return None;
+        }
+
+        let reason = self.check_before(cpu);
+
+        if reason.is_some() {
+            self.stopped_at = Some(addr);
+        }
+
+        reason
+    }
+
+    /// Checks breakpoints, watchpoints and opcode breaks for the next instruction.
+    pub fn check_before(&self, cpu: &Cpu) -> Option<StopReason> {
+        let addr = cpu.get_program_counter();
+
+        if self.breakpoints.contains(&addr) {
+            return Some(StopReason::Breakpoint(addr));
+        }
+
+        let opcode = decode(cpu, addr);
+
+        if !self.break_opcodes.is_empty() {
+            let kind = opcode_kind(opcode.as_ref());
+
+            if self.break_opcodes.contains(&kind) {
+                return Some(StopReason::Opcode(kind));
+            }
+        }
+
+        if let Some(opcode) = opcode.as_ref() {
+            for (range, access) in memory_accesses(cpu, opcode) {
+                let hit = self.watchpoints.iter().find_map(|watchpoint| {
+                    let overlaps = range.start() <= watchpoint.range.end() && watchpoint.range.start() <= range.end();
+
+                    if overlaps && watchpoint.kind.matches(access) {
+                        Some(*range.start().max(watchpoint.range.start()))
+                    } else {
+                        None
+                    }
+                });
+
+                if let Some(address) = hit {
+                    return Some(StopReason::Watchpoint { address, access });
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Executes one instruction and checks the conditions that apply after it.
+    fn execute(&mut self, cpu: &mut Cpu) -> Option<StopReason> {
+        self.stopped_at = None;
+
+        let was_drawing = matches!(decode(cpu, cpu.get_program_counter()), Some(OpCode::DrawSprite(..)));
+        let met_before: Vec<bool> = self.conditions.iter().map(|c| c.is_met(cpu)).collect();
+
+        if let Err(fault) = cpu.step() {
+            return Some(StopReason::Fault(fault));
+        }
+
+        if cpu.has_exited() {
+            return Some(StopReason::Exited);
+        }
+
+        if self.break_on_collision && was_drawing && cpu.get_registers()[0xF] == 1 {
+            return Some(StopReason::Collision);
+        }
+
+        self.conditions
+            .iter()
+            .zip(met_before)
+            .find(|(condition, met_before)| !met_before && condition.is_met(cpu))
+            .map(|(&condition, _)| StopReason::Condition(condition))
+    }
+}
+
+/// Decodes the instruction at an address.
+pub fn decode(cpu: &Cpu, addr: usize) -> Option<OpCode> {
+    disassembler::disassemble_word(read_word(cpu, addr))
+}
+
+/// Reads the big-endian word at an address, wrapping around the end of memory.
+pub fn read_word(cpu: &Cpu, addr: usize) -> u16 {
+    let memory = cpu.get_memory();
+
+    u16::from_be_bytes([memory[addr % memory.len()], memory[(addr + 1) % memory.len()]])
+}
+
+/// Gets the name of an opcode's kind, like `"DrawSprite"`, or `"Unknown"` for words that don't decode.
+pub fn opcode_kind(opcode: Option<&OpCode>) -> String {
+    match opcode {
+        Some(opcode) => opcode.get_name().to_string(),
+        None => String::from("Unknown")
+    }
+}
+
+/// Works out which memory an instruction will access from the CPU's current state.
+fn memory_accesses(cpu: &Cpu, opcode: &OpCode) -> Vec<(RangeInclusive<usize>, Access)> {
+    let index = cpu.get_index() as usize;
+    let range = |len: usize| index..=index + len - 1;
+
+    match *opcode {
+        OpCode::DrawSprite(_, _, n) => {
+            let len = if n == 0 { 32 } else { n as usize };
+            let planes = cpu.get_display().get_selected_plane_count();
+
+            if planes == 0 {
+                Vec::new()
+            } else {
+                vec![(range(len * planes), Access::Read)]
+            }
+        }
+        OpCode::StoreBcd(_) => vec![(range(3), Access::Write)],
+        OpCode::RegDump(x) => vec![(range(x as usize + 1), Access::Write)],
+        OpCode::RegLoad(x) => vec![(range(x as usize + 1), Access::Read)],
+        OpCode::SaveRange(x, y) => vec![(range((x as isize - y as isize).unsigned_abs() + 1), Access::Write)],
+        OpCode::LoadRange(x, y) => vec![(range((x as isize - y as isize).unsigned_abs() + 1), Access::Read)],
+        OpCode::LoadAudioPattern => vec![(range(16), Access::Read)],
+        _ => Vec::new()
+    }
+}
@@ -0,0 +1,250 @@
+// opcode definitions here: https://en.wikipedia.org/wiki/CHIP-8
+
+#[derive(Debug)]
+pub enum OpCode {
+    MachineCall(u16), // 0NNN
+    ScrollDown(u8), // 00CN (SUPER-CHIP)
+    ScrollUp(u8), // 00DN (XO-CHIP)
+    ClearDisplay, // 00E0
+    Return, // 00EE
+    ScrollRight, // 00FB (SUPER-CHIP)
+    ScrollLeft, // 00FC (SUPER-CHIP)
+    Exit, // 00FD (SUPER-CHIP)
+    LowRes, // 00FE (SUPER-CHIP)
+    HighRes, // 00FF (SUPER-CHIP)
+    Jump(u16), // 1NNN
+    Call(u16), // 2NNN
+    SkipEqVal(u8, u8), // 3XNN
+    SkipNotEqVal(u8, u8), // 4XNN
+    SkipEq(u8, u8), // 5XY0
+    SaveRange(u8, u8), // 5XY2 (XO-CHIP)
+    LoadRange(u8, u8), // 5XY3 (XO-CHIP)
+    SetVal(u8, u8), // 6XNN
+    AddVal(u8, u8), // 7XNN
+    Copy(u8, u8), // 8XY0
+    Or(u8, u8), // 8XY1
+    And(u8, u8), // 8XY2
+    Xor(u8, u8), // 8XY3
+    Add(u8, u8), // 8XY4
+    Subtract(u8, u8), // 8XY5
+    ShiftRight(u8, u8), // 8XY6
+    Difference(u8, u8), // 8XY7
+    ShiftLeft(u8, u8), // 8XYE
+    SkipNotEq(u8, u8), // 9XY0
+    SetIndex(u16), // ANNN
+    JumpOffset(u16), // BNNN
+    Rand(u8, u8), // CXNN
+    DrawSprite(u8, u8, u8), // DXYN, or DXY0 for a 16x16 sprite (SUPER-CHIP)
+    SkipKeyPressed(u8), // EX9E
+    SkipKeyNotPressed(u8), // EXA1
+    GetDelayTimer(u8), // FX07
+    SetIndexLong, // F000 NNNN (XO-CHIP), the address is the following word
+    SelectPlanes(u8), // FN01 (XO-CHIP)
+    LoadAudioPattern, // F002 (XO-CHIP)
+    GetKeyPress(u8), // FX0A
+    SetDelayTimer(u8), // FX15
+    SetSoundTimer(u8), // FX18
+    AddIndex(u8), // FX1E
+    SetIndexCharacter(u8), // FX29
+    SetIndexBigCharacter(u8), // FX30 (SUPER-CHIP)
+    SetPitch(u8), // FX3A (XO-CHIP)
+    StoreBcd(u8), // FX33
+    RegDump(u8), // FX55
+    RegLoad(u8), // FX65
+    StoreFlags(u8), // FX75 (SUPER-CHIP)
+    LoadFlags(u8) // FX85 (SUPER-CHIP)
+}
+
+impl OpCode {
+    /// Gets the name of the opcode's kind, the same as the variant, e.g. `"DrawSprite"`.
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            OpCode::MachineCall(..) => "MachineCall",
+            OpCode::ScrollDown(..) => "ScrollDown",
+            OpCode::ScrollUp(..) => "ScrollUp",
+            OpCode::ClearDisplay => "ClearDisplay",
+            OpCode::Return => "Return",
+            OpCode::ScrollRight => "ScrollRight",
+            OpCode::ScrollLeft => "ScrollLeft",
+            OpCode::Exit => "Exit",
+            OpCode::LowRes => "LowRes",
+            OpCode::HighRes => "HighRes",
+            OpCode::Jump(..) => "Jump",
+            OpCode::Call(..) => "Call",
+            OpCode::SkipEqVal(..) => "SkipEqVal",
+            OpCode::SkipNotEqVal(..) => "SkipNotEqVal",
+            OpCode::SkipEq(..) => "SkipEq",
+            OpCode::SaveRange(..) => "SaveRange",
+            OpCode::LoadRange(..) => "LoadRange",
+            OpCode::SetVal(..) => "SetVal",
+            OpCode::AddVal(..) => "AddVal",
+            OpCode::Copy(..) => "Copy",
+            OpCode::Or(..) => "Or",
+            OpCode::And(..) => "And",
+            OpCode::Xor(..) => "Xor",
+            OpCode::Add(..) => "Add",
+            OpCode::Subtract(..) => "Subtract",
+            OpCode::ShiftRight(..) => "ShiftRight",
+            OpCode::Difference(..) => "Difference",
+            OpCode::ShiftLeft(..) => "ShiftLeft",
+            OpCode::SkipNotEq(..) => "SkipNotEq",
+            OpCode::SetIndex(..) => "SetIndex",
+            OpCode::JumpOffset(..) => "JumpOffset",
+            OpCode::Rand(..) => "Rand",
+            OpCode::DrawSprite(..) => "DrawSprite",
+            OpCode::SkipKeyPressed(..) => "SkipKeyPressed",
+            OpCode::SkipKeyNotPressed(..) => "SkipKeyNotPressed",
+            OpCode::GetDelayTimer(..) => "GetDelayTimer",
+            OpCode::SetIndexLong => "SetIndexLong",
+            OpCode::SelectPlanes(..) => "SelectPlanes",
+            OpCode::LoadAudioPattern => "LoadAudioPattern",
+            OpCode::GetKeyPress(..) => "GetKeyPress",
+            OpCode::SetDelayTimer(..) => "SetDelayTimer",
+            OpCode::SetSoundTimer(..) => "SetSoundTimer",
+            OpCode::AddIndex(..) => "AddIndex",
+            OpCode::SetIndexCharacter(..) => "SetIndexCharacter",
+            OpCode::SetIndexBigCharacter(..) => "SetIndexBigCharacter",
+            OpCode::SetPitch(..) => "SetPitch",
+            OpCode::StoreBcd(..) => "StoreBcd",
+            OpCode::RegDump(..) => "RegDump",
+            OpCode::RegLoad(..) => "RegLoad",
+            OpCode::StoreFlags(..) => "StoreFlags",
+            OpCode::LoadFlags(..) => "LoadFlags"
+        }
+    }
+
+    /// Gets the opcode's operands in the order they appear in the variant, e.g. `[x, y, n]` for
+    /// `DXYN`. Register operands are register numbers, not their values.
+    pub fn get_operands(&self) -> Vec<u16> {
+        match *self {
+            OpCode::MachineCall(nnn)
+            | OpCode::Jump(nnn)
+            | OpCode::Call(nnn)
+            | OpCode::SetIndex(nnn)
+            | OpCode::JumpOffset(nnn) => vec![nnn],
+            OpCode::ScrollDown(x)
+            | OpCode::ScrollUp(x)
+            | OpCode::SkipKeyPressed(x)
+            | OpCode::SkipKeyNotPressed(x)
+            | OpCode::GetDelayTimer(x)
+            | OpCode::SelectPlanes(x)
+            | OpCode::GetKeyPress(x)
+            | OpCode::SetDelayTimer(x)
+            | OpCode::SetSoundTimer(x)
+            | OpCode::AddIndex(x)
+            | OpCode::SetIndexCharacter(x)
+            | OpCode::SetIndexBigCharacter(x)
+            | OpCode::SetPitch(x)
+            | OpCode::StoreBcd(x)
+            | OpCode::RegDump(x)
+            | OpCode::RegLoad(x)
+            | OpCode::StoreFlags(x)
+            | OpCode::LoadFlags(x) => vec![x as u16],
+            OpCode::SkipEqVal(x, y)
+            | OpCode::SkipNotEqVal(x, y)
+            | OpCode::SkipEq(x, y)
+            | OpCode::SaveRange(x, y)
+            | OpCode::LoadRange(x, y)
+            | OpCode::SetVal(x, y)
+            | OpCode::AddVal(x, y)
+            | OpCode::Copy(x, y)
+            | OpCode::Or(x, y)
+            | OpCode::And(x, y)
+            | OpCode::Xor(x, y)
+            | OpCode::Add(x, y)
+            | OpCode::Subtract(x, y)
+            | OpCode::ShiftRight(x, y)
+            | OpCode::Difference(x, y)
+            | OpCode::ShiftLeft(x, y)
+            | OpCode::SkipNotEq(x, y)
+            | OpCode::Rand(x, y) => vec![x as u16, y as u16],
+            OpCode::DrawSprite(x, y, n) => vec![x as u16, y as u16, n as u16],
+            OpCode::ClearDisplay
+            | OpCode::Return
+            | OpCode::ScrollRight
+            | OpCode::ScrollLeft
+            | OpCode::Exit
+            | OpCode::LowRes
+            | OpCode::HighRes
+            | OpCode::SetIndexLong
+            | OpCode::LoadAudioPattern => Vec::new()
+        }
+    }
+}
+
+/// Decodes a buffer of big-endian instruction words.
+pub fn disassemble_bytes(bytes: &[u8]) -> Vec<Option<OpCode>> {
+    bytes
+        .chunks_exact(2)
+        .map(|word| disassemble_word(u16::from_be_bytes([word[0], word[1]])))
+        .collect()
+}
+
+/// Decodes a single instruction word, returning `None` if it is not a known opcode.
+pub fn disassemble_word(word: u16) -> Option<OpCode> {
+    let op_1 = ((word & 0xF000) >> 12) as u8;
+    let op_2: u8 = ((word & 0x0F00) >> 8) as u8;
+    let op_3: u8 = ((word & 0x00F0) >> 4) as u8;
+    let op_4: u8 = (word & 0x000F) as u8;
+
+    let x = op_2;
+    let y = op_3;
+    let nnn = word & 0x0FFF;
+    let nn = (word & 0x00FF) as u8;
+    let n = op_4;
+
+    match (op_1, op_2, op_3, op_4) {
+        (0x0, 0x0, 0xC, _) => Some(OpCode::ScrollDown(n)),
+        (0x0, 0x0, 0xD, _) => Some(OpCode::ScrollUp(n)),
+        (0x0, 0x0, 0xE, 0x0) => Some(OpCode::ClearDisplay),
+        (0x0, 0x0, 0xE, 0xE) => Some(OpCode::Return),
+        (0x0, 0x0, 0xF, 0xB) => Some(OpCode::ScrollRight),
+        (0x0, 0x0, 0xF, 0xC) => Some(OpCode::ScrollLeft),
+        (0x0, 0x0, 0xF, 0xD) => Some(OpCode::Exit),
+        (0x0, 0x0, 0xF, 0xE) => Some(OpCode::LowRes),
+        (0x0, 0x0, 0xF, 0xF) => Some(OpCode::HighRes),
+        (0x0, _, _, _) => Some(OpCode::MachineCall(nnn)),
+        (0x1, _, _, _) => Some(OpCode::Jump(nnn)),
+        (0x2, _, _, _) => Some(OpCode::Call(nnn)),
+        (0x3, _, _, _) => Some(OpCode::SkipEqVal(x, nn)),
+        (0x4, _, _, _) => Some(OpCode::SkipNotEqVal(x, nn)),
+        (0x5, _, _, 0x0) => Some(OpCode::SkipEq(x, y)),
+        (0x5, _, _, 0x2) => Some(OpCode::SaveRange(x, y)),
+        (0x5, _, _, 0x3) => Some(OpCode::LoadRange(x, y)),
+        (0x6, _, _, _) => Some(OpCode::SetVal(x, nn)),
+        (0x7, _, _, _) => Some(OpCode::AddVal(x, nn)),
+        (0x8, _, _, 0x0) => Some(OpCode::Copy(x, y)),
+        (0x8, _, _, 0x1) => Some(OpCode::Or(x, y)),
+        (0x8, _, _, 0x2) => Some(OpCode::And(x, y)),
+        (0x8, _, _, 0x3) => Some(OpCode::Xor(x, y)),
+        (0x8, _, _, 0x4) => Some(OpCode::Add(x, y)),
+        (0x8, _, _, 0x5) => Some(OpCode::Subtract(x, y)),
+        (0x8, _, _, 0x6) => Some(OpCode::ShiftRight(x, y)),
+        (0x8, _, _, 0x7) => Some(OpCode::Difference(x, y)),
+        (0x8, _, _, 0xE) => Some(OpCode::ShiftLeft(x, y)),
+        (0x9, _, _, 0x0) => Some(OpCode::SkipNotEq(x, y)),
+        (0xA, _, _, _) => Some(OpCode::SetIndex(nnn)),
+        (0xB, _, _, _) => Some(OpCode::JumpOffset(nnn)),
+        (0xC, _, _, _) => Some(OpCode::Rand(x, nn)),
+        (0xD, _, _, _) => Some(OpCode::DrawSprite(x, y, n)),
+        (0xE, _, 0x9, 0xE) => Some(OpCode::SkipKeyPressed(x)),
+        (0xE, _, 0xA, 0x1) => Some(OpCode::SkipKeyNotPressed(x)),
+        (0xF, 0x0, 0x0, 0x0) => Some(OpCode::SetIndexLong),
+        (0xF, _, 0x0, 0x1) => Some(OpCode::SelectPlanes(x)),
+        (0xF, 0x0, 0x0, 0x2) => Some(OpCode::LoadAudioPattern),
+        (0xF, _, 0x0, 0x7) => Some(OpCode::GetDelayTimer(x)),
+        (0xF, _, 0x0, 0xA) => Some(OpCode::GetKeyPress(x)),
+        (0xF, _, 0x1, 0x5) => Some(OpCode::SetDelayTimer(x)),
+        (0xF, _, 0x1, 0x8) => Some(OpCode::SetSoundTimer(x)),
+        (0xF, _, 0x1, 0xE) => Some(OpCode::AddIndex(x)),
+        (0xF, _, 0x2, 0x9) => Some(OpCode::SetIndexCharacter(x)),
+        (0xF, _, 0x3, 0x0) => Some(OpCode::SetIndexBigCharacter(x)),
+        (0xF, _, 0x3, 0xA) => Some(OpCode::SetPitch(x)),
+        (0xF, _, 0x3, 0x3) => Some(OpCode::StoreBcd(x)),
+        (0xF, _, 0x5, 0x5) => Some(OpCode::RegDump(x)),
+        (0xF, _, 0x6, 0x5) => Some(OpCode::RegLoad(x)),
+        (0xF, _, 0x7, 0x5) => Some(OpCode::StoreFlags(x)),
+        (0xF, _, 0x8, 0x5) => Some(OpCode::LoadFlags(x)),
+        _ => None
+    }
+}
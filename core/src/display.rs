@@ -0,0 +1,279 @@
+use crate::savestate::{SaveStateError, StateReader, StateWriter};
+
+/// Width of the standard low resolution display.
+pub const WIDTH: u8 = 64;
+/// Height of the standard low resolution display.
+pub const HEIGHT: u8 = 32;
+
+/// Width of the SUPER-CHIP high resolution display.
+pub const HIRES_WIDTH: u8 = 128;
+/// Height of the SUPER-CHIP high resolution display.
+pub const HIRES_HEIGHT: u8 = 64;
+
+/// Number of XO-CHIP bitplanes. Each pixel's colour is made of one bit from each plane.
+pub const PLANE_COUNT: usize = 2;
+
+const SIZE: usize = HIRES_WIDTH as usize * HIRES_HEIGHT as usize;
+
+/// Frame buffer, either 64x32 or 128x64 in SUPER-CHIP high resolution mode.
+///
+/// There are two XO-CHIP bitplanes, giving four colours. Drawing, scrolling and clearing only
+/// affect the selected planes, and only the first plane is selected by default, so classic
+/// programs behave as if the display were monochrome.
+pub struct DisplayBuffer {
+    planes: [[bool; SIZE]; PLANE_COUNT],
+    selected_planes: u8,
+    hires: bool
+}
+
+impl DisplayBuffer {
+    pub fn new() -> Self {
+        DisplayBuffer {
+            planes: [[false; SIZE]; PLANE_COUNT],
+            selected_planes: 0b01,
+            hires: false
+        }
+    }
+
+    /// Gets the width of the current resolution.
+    pub fn get_width(&self) -> u8 {
+        if self.hires { HIRES_WIDTH } else { WIDTH }
+    }
+
+    /// Gets the height of the current resolution.
+    pub fn get_height(&self) -> u8 {
+        if self.hires { HIRES_HEIGHT } else { HEIGHT }
+    }
+
+    /// Checks if the display is in high resolution mode.
+    pub fn is_hires(&self) -> bool {
+        self.hires
+    }
+
+    /// Switches between low and high resolution, clearing every plane.
+    pub fn set_hires(&mut self, hires: bool) {
+        self.hires = hires;
+
+        for plane in self.planes.iter_mut() {
+            plane.iter_mut().for_each(|x| *x = false);
+        }
+    }
+
+    /// Gets the bitmask of planes affected by drawing, scrolling and clearing.
+    pub fn get_selected_planes(&self) -> u8 {
+        self.selected_planes
+    }
+
+    /// Selects the planes affected by drawing, scrolling and clearing with a bitmask.
+    pub fn select_planes(&mut self, mask: u8) {
+        self.selected_planes = mask & ((1 << PLANE_COUNT) - 1);
+    }
+
+    /// Gets the number of selected planes, which is how many copies of a sprite's data a draw consumes.
+    pub fn get_selected_plane_count(&self) -> usize {
+        self.selected_planes.count_ones() as usize
+    }
+
+    /// Indices of the selected planes, in order.
+    fn selected_plane_indices(&self) -> impl Iterator<Item = usize> {
+        let mask = self.selected_planes;
+
+        (0..PLANE_COUNT).filter(move |plane| mask & (1 << plane) != 0)
+    }
+
+    /// Checks if a pixel is set on any plane by index, in rows of the current width.
+    pub fn is_set(&self, index: usize) -> bool {
+        self.planes.iter().any(|plane| plane[index])
+    }
+
+    /// Checks if a pixel is set on any plane by coordinates in the current resolution.
+    pub fn is_set_at(&self, x: u8, y: u8) -> bool {
+        self.is_set(x as usize + y as usize * self.get_width() as usize)
+    }
+
+    /// Gets the colour of a pixel as a bitmask of the planes it is set on.
+    pub fn get_color_at(&self, x: u8, y: u8) -> u8 {
+        let index = x as usize + y as usize * self.get_width() as usize;
+
+        self.planes
+            .iter()
+            .enumerate()
+            .fold(0, |color, (i, plane)| color | ((plane[index] as u8) << i))
+    }
+
+    /// XORs a pixel on a plane and returns if there was a collision.
+    fn xor_pixel(&mut self, plane: usize, x: u8, y: u8) -> bool {
+        if x >= self.get_width() || y >= self.get_height() {
+            return false;
+        }
+
+        let index = x as usize + (y as usize * self.get_width() as usize);
+
+        let old = self.planes[plane][index];
+        let new = old ^ true;
+
+        self.planes[plane][index] = new;
+
+        // there is a collision if the pixel was on, but is now off
+        old && !new
+    }
+
+    /// Draws an 8 pixel wide sprite and returns if there was a collision.
+    ///
+    /// The starting position always wraps around the screen. Pixels that go past the edge are
+    /// either clipped or wrapped around to the other side.
+    ///
+    /// With several planes selected, `bytes` holds one sprite per plane, one after another.
+    pub fn draw_sprite(&mut self, x: u8, y: u8, bytes: &[u8], clip: bool) -> bool {
+        self.draw_planes(bytes, |display, plane, bytes| {
+            let rows = bytes.iter().map(|&byte| (byte as u16) << 8);
+
+            display.draw_rows(plane, x, y, rows, clip)
+        })
+    }
+
+    /// Draws a SUPER-CHIP 16x16 sprite from 32 bytes per plane, two per row, and returns if there was a collision.
+    pub fn draw_large_sprite(&mut self, x: u8, y: u8, bytes: &[u8], clip: bool) -> bool {
+        self.draw_planes(bytes, |display, plane, bytes| {
+            let rows = bytes
+                .chunks_exact(2)
+                .map(|row| u16::from_be_bytes([row[0], row[1]]));
+
+            display.draw_rows(plane, x, y, rows, clip)
+        })
+    }
+
+    /// Splits sprite data evenly between the selected planes and draws each part.
+    fn draw_planes<F>(&mut self, bytes: &[u8], mut draw: F) -> bool
+        where F: FnMut(&mut Self, usize, &[u8]) -> bool
+    {
+        let count = self.get_selected_plane_count();
+
+        if count == 0 {
+            return false;
+        }
+
+        let len = bytes.len() / count;
+        let planes: Vec<usize> = self.selected_plane_indices().collect();
+        let mut collision = false;
+
+        for (plane, bytes) in planes.into_iter().zip(bytes.chunks(len.max(1))) {
+            collision |= draw(self, plane, bytes);
+        }
+
+        collision
+    }
+
+    /// Draws rows of up to 16 pixels on a plane, most significant bit first.
+    fn draw_rows(&mut self, plane: usize, x: u8, y: u8, rows: impl Iterator<Item = u16>, clip: bool) -> bool {
+        let width = self.get_width() as usize;
+        let height = self.get_height() as usize;
+        let x = x as usize % width;
+        let y = y as usize % height;
+
+        let mut collision = false;
+
+        for (row, bits) in rows.enumerate() {
+            let current_y = y + row;
+
+            if clip && current_y >= height {
+                break;
+            }
+
+            for bit in 0..16 {
+                let current_x = x + bit;
+
+                if clip && current_x >= width {
+                    break;
+                }
+
+                if (bits >> (15 - bit)) & 0x01 > 0
+                    && self.xor_pixel(plane, (current_x % width) as u8, (current_y % height) as u8) {
+                    collision = true;
+                }
+            }
+        }
+
+        collision
+    }
+
+    /// Scrolls the selected planes down by a number of pixels, filling the top with blank rows.
+    pub fn scroll_down(&mut self, amount: u8) {
+        self.scroll(0, amount as isize);
+    }
+
+    /// Scrolls the selected planes up by a number of pixels, filling the bottom with blank rows.
+    pub fn scroll_up(&mut self, amount: u8) {
+        self.scroll(0, -(amount as isize));
+    }
+
+    /// Scrolls the selected planes right by a number of pixels, filling the left with blank columns.
+    pub fn scroll_right(&mut self, amount: u8) {
+        self.scroll(amount as isize, 0);
+    }
+
+    /// Scrolls the selected planes left by a number of pixels, filling the right with blank columns.
+    pub fn scroll_left(&mut self, amount: u8) {
+        self.scroll(-(amount as isize), 0);
+    }
+
+    /// Moves the selected planes by an offset, filling uncovered pixels with blanks.
+    fn scroll(&mut self, dx: isize, dy: isize) {
+        let width = self.get_width() as isize;
+        let height = self.get_height() as isize;
+        let planes: Vec<usize> = self.selected_plane_indices().collect();
+
+        for plane in planes {
+            let old = self.planes[plane];
+
+            for y in 0..height {
+                for x in 0..width {
+                    let (src_x, src_y) = (x - dx, y - dy);
+                    let inside = src_x >= 0 && src_x < width && src_y >= 0 && src_y < height;
+
+                    self.planes[plane][(x + y * width) as usize] = inside && old[(src_x + src_y * width) as usize];
+                }
+            }
+        }
+    }
+
+    /// Writes the resolution, plane selection and pixels to a save state.
+    pub(crate) fn write_state(&self, writer: &mut StateWriter) {
+        writer.write_bool(self.hires);
+        writer.write_u8(self.selected_planes);
+
+        for plane in self.planes.iter() {
+            writer.write_bits(plane);
+        }
+    }
+
+    /// Reads a display written by [`DisplayBuffer::write_state`].
+    pub(crate) fn read_state(reader: &mut StateReader) -> Result<Self, SaveStateError> {
+        let mut display = DisplayBuffer::new();
+
+        display.hires = reader.read_bool()?;
+        display.select_planes(reader.read_u8()?);
+
+        for plane in display.planes.iter_mut() {
+            reader.read_bits(plane)?;
+        }
+
+        Ok(display)
+    }
+
+    /// Clears all pixels on the selected planes
+    pub fn clear(&mut self) {
+        let planes: Vec<usize> = self.selected_plane_indices().collect();
+
+        for plane in planes {
+            self.planes[plane].iter_mut()
+                .for_each(|x| *x = false);
+        }
+    }
+}
+
+impl Default for DisplayBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
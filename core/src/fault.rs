@@ -0,0 +1,127 @@
+use std::error::Error;
+use std::fmt;
+
+/// The reason a CPU fault was raised.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultKind {
+    /// `2NNN` was executed with all 16 stack slots in use.
+    StackOverflow,
+    /// `00EE` was executed with an empty stack.
+    StackUnderflow,
+    /// An instruction read or wrote memory past the end of the address space.
+    MemoryOutOfBounds,
+    /// `FX1E` overflowed the 16-bit index register.
+    IndexOverflow,
+    /// The program counter ran off the end of memory.
+    ProgramCounterOutOfBounds,
+    /// The instruction word does not decode to any known opcode.
+    UnknownOpcode,
+    /// `0NNN` tried to call a native machine code routine, which can't be emulated.
+    MachineCodeCall,
+    /// `EX9E`/`EXA1` checked a key above F.
+    InvalidKey
+}
+
+impl fmt::Display for FaultKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            FaultKind::StackOverflow => "stack overflow",
+            FaultKind::StackUnderflow => "stack underflow",
+            FaultKind::MemoryOutOfBounds => "memory access out of bounds",
+            FaultKind::IndexOverflow => "index register overflow",
+            FaultKind::ProgramCounterOutOfBounds => "program counter out of bounds",
+            FaultKind::UnknownOpcode => "unknown opcode",
+            FaultKind::MachineCodeCall => "machine code call",
+            FaultKind::InvalidKey => "invalid key"
+        };
+
+        f.write_str(reason)
+    }
+}
+
+/// A fault raised while executing an instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpuFault {
+    /// Address of the faulting instruction.
+    pub address: usize,
+    /// Raw instruction word, or 0 if it could not be fetched.
+    pub word: u16,
+    /// Why the fault was raised.
+    pub kind: FaultKind
+}
+
+impl fmt::Display for CpuFault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at 0x{:03X} ({:04X})", self.kind, self.address, self.word)
+    }
+}
+
+impl Error for CpuFault {}
+
+/// What the CPU does when a fault is raised.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultAction {
+    /// Stop execution and report the fault. The program counter is left on the faulting instruction.
+    Halt,
+    /// Wrap the offending stack pointer, address or index around and carry on.
+    Wrap,
+    /// Skip the offending instruction and carry on.
+    Ignore
+}
+
+/// The action to take for each kind of fault.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FaultPolicy {
+    pub stack_overflow: FaultAction,
+    pub stack_underflow: FaultAction,
+    pub memory_out_of_bounds: FaultAction,
+    pub index_overflow: FaultAction,
+    /// There is no instruction to skip when the program counter runs off the end of memory,
+    /// so `Ignore` behaves like `Halt` here.
+    pub program_counter_out_of_bounds: FaultAction,
+    /// `Ignore` skips the word. There is nothing to wrap, so `Wrap` behaves like `Ignore`.
+    pub unknown_opcode: FaultAction,
+    /// `Ignore` treats `0NNN` as a no-op like the COSMAC VIP did for most programs.
+    /// There is nothing to wrap, so `Wrap` behaves like `Ignore`.
+    pub machine_code_call: FaultAction,
+    /// `Wrap` checks the low 4 bits of the key. Skipping a key check would still have to
+    /// decide whether to skip, so `Ignore` behaves like `Wrap`.
+    pub invalid_key: FaultAction
+}
+
+impl FaultPolicy {
+    /// Creates a policy that takes the same action for every kind of fault.
+    pub fn all(action: FaultAction) -> Self {
+        FaultPolicy {
+            stack_overflow: action,
+            stack_underflow: action,
+            memory_out_of_bounds: action,
+            index_overflow: action,
+            program_counter_out_of_bounds: action,
+            unknown_opcode: action,
+            machine_code_call: action,
+            invalid_key: action
+        }
+    }
+
+    /// Gets the action to take for a kind of fault.
+    pub fn action_for(&self, kind: FaultKind) -> FaultAction {
+        match kind {
+            FaultKind::StackOverflow => self.stack_overflow,
+            FaultKind::StackUnderflow => self.stack_underflow,
+            FaultKind::MemoryOutOfBounds => self.memory_out_of_bounds,
+            FaultKind::IndexOverflow => self.index_overflow,
+            FaultKind::ProgramCounterOutOfBounds => self.program_counter_out_of_bounds,
+            FaultKind::UnknownOpcode => self.unknown_opcode,
+            FaultKind::MachineCodeCall => self.machine_code_call,
+            FaultKind::InvalidKey => self.invalid_key
+        }
+    }
+}
+
+impl Default for FaultPolicy {
+    /// Halts on every fault.
+    fn default() -> Self {
+        FaultPolicy::all(FaultAction::Halt)
+    }
+}
@@ -0,0 +1,763 @@
+use crate::cpu::Cpu;
+use crate::debugger::{Access, Debugger, StopReason, WatchKind, Watchpoint};
+use crate::fault::FaultKind;
+
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+
+/// Port gdb is usually pointed at with `target remote :1234`.
+pub const DEFAULT_PORT: u16 = 1234;
+
+/// Register layout sent to gdb, in order, with each register's size in bytes.
+///
+/// gdb has no CHIP-8 architecture, so the target description can't name one and gdb falls back to
+/// its default, which is little-endian on the usual hosts. Register values are sent little-endian
+/// to match, even though instruction words in memory are big-endian.
+const REGISTERS: [(&str, usize); 21] = [
+    ("v0", 1), ("v1", 1), ("v2", 1), ("v3", 1), ("v4", 1), ("v5", 1), ("v6", 1), ("v7", 1),
+    ("v8", 1), ("v9", 1), ("va", 1), ("vb", 1), ("vc", 1), ("vd", 1), ("ve", 1), ("vf", 1),
+    ("i", 2),
+    ("pc", 2),
+    ("sp", 1),
+    ("dt", 1),
+    ("st", 1)
+];
+
+const PC_REGISTER: usize = 17;
+
+/// Largest packet we accept, advertised to gdb in hex.
+const PACKET_SIZE: usize = 0x1000;
+
+// signal numbers used in stop replies
+const SIGINT: u8 = 2;
+const SIGILL: u8 = 4;
+const SIGTRAP: u8 = 5;
+const SIGSEGV: u8 = 11;
+
+/// Whether the CPU should be running between polls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetState {
+    /// gdb has the CPU stopped, or nothing has attached yet.
+    Stopped,
+    /// gdb continued, so the frontend should run frames until something breaks.
+    Running,
+    /// gdb asked to kill the program.
+    Killed
+}
+
+/// Serves the CPU to a debugger speaking the GDB remote serial protocol over TCP.
+///
+/// Everything is non-blocking so the stub can be polled from a frontend's main loop. The CPU starts
+/// stopped and waits for gdb to attach and continue. Breakpoints and watchpoints set from gdb are
+/// added to the frontend's [`Debugger`], so the frontend reports stops with [`GdbStub::report_stop`].
+pub struct GdbStub {
+    listener: TcpListener,
+    stream: Option<TcpStream>,
+    session: Session
+}
+
+impl GdbStub {
+    /// Starts listening for gdb on an address, e.g. `("127.0.0.1", DEFAULT_PORT)`.
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+
+        listener.set_nonblocking(true)?;
+
+        Ok(GdbStub {
+            listener,
+            stream: None,
+            session: Session::new()
+        })
+    }
+
+    pub fn get_state(&self) -> TargetState {
+        self.session.state
+    }
+
+    /// Checks if gdb is attached.
+    pub fn is_connected(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /// Accepts a connection and handles every packet that has arrived, without blocking.
+    pub fn poll(&mut self, cpu: &mut Cpu, debugger: &mut Debugger) -> io::Result<()> {
+        if self.stream.is_none() {
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    stream.set_nonblocking(true)?;
+                    stream.set_nodelay(true)?;
+
+                    self.stream = Some(stream);
+                    self.session = Session::new();
+                }
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                Err(err) => return Err(err)
+            }
+        }
+
+        if !self.receive()? {
+            // gdb went away without detaching, so let the program carry on by itself
+            self.stream = None;
+            self.session.state = TargetState::Running;
+
+            return Ok(());
+        }
+
+        if let Some(stream) = self.stream.as_mut() {
+            self.session.handle_packets(&mut BlockingWriter(stream), cpu, debugger)?;
+        }
+
+        if self.session.detached {
+            self.stream = None;
+        }
+
+        Ok(())
+    }
+
+    /// Tells gdb why the CPU stopped after it continued.
+    pub fn report_stop(&mut self, cpu: &Cpu, reason: &StopReason) -> io::Result<()> {
+        if self.session.state == TargetState::Running {
+            self.session.state = TargetState::Stopped;
+        }
+
+        match self.stream.as_mut() {
+            Some(stream) => self.session.send_packet(&mut BlockingWriter(stream), &stop_reply(cpu, reason)),
+            None => Ok(())
+        }
+    }
+
+    /// Reads whatever has arrived into the buffer. Returns false if the connection closed.
+    fn receive(&mut self) -> io::Result<bool> {
+        let stream = match self.stream.as_mut() {
+            Some(stream) => stream,
+            None => return Ok(true)
+        };
+        let mut chunk = [0; 1024];
+
+        loop {
+            match stream.read(&mut chunk) {
+                Ok(0) => return Ok(false),
+                Ok(len) => self.session.buffer.extend_from_slice(&chunk[..len]),
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(true),
+                Err(err) if err.kind() == io::ErrorKind::ConnectionReset => return Ok(false),
+                Err(err) => return Err(err)
+            }
+        }
+    }
+}
+
+/// Writes to gdb, blocking until everything is sent since replies are small.
+struct BlockingWriter<'a>(&'a mut TcpStream);
+
+impl Write for BlockingWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.set_nonblocking(false)?;
+
+        let result = self.0.write(buf);
+
+        self.0.set_nonblocking(true)?;
+
+        result
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+/// The protocol state of one connection. Replies go to whatever writer it's given, so it doesn't
+/// depend on the socket.
+struct Session {
+    /// Bytes received but not handled yet.
+    buffer: Vec<u8>,
+    last_packet: Vec<u8>,
+    no_ack: bool,
+    state: TargetState,
+    /// Set once gdb detaches or kills the program, after which the connection should be dropped.
+    detached: bool
+}
+
+impl Session {
+    fn new() -> Self {
+        Session {
+            buffer: Vec::new(),
+            last_packet: Vec::new(),
+            no_ack: false,
+            state: TargetState::Stopped,
+            detached: false
+        }
+    }
+
+    /// Handles every complete packet in the buffer.
+    fn handle_packets<W: Write>(&mut self, out: &mut W, cpu: &mut Cpu, debugger: &mut Debugger) -> io::Result<()> {
+        while !self.detached {
+            match self.next_packet(out)? {
+                Some(packet) => self.handle_packet(out, &packet, cpu, debugger)?,
+                None => break
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Takes the next complete packet out of the buffer, handling acks and interrupts on the way.
+    fn next_packet<W: Write>(&mut self, out: &mut W) -> io::Result<Option<String>> {
+        loop {
+            match self.buffer.first() {
+                None => return Ok(None),
+                Some(b'+') => {
+                    self.buffer.remove(0);
+                }
+                Some(b'-') => {
+                    // gdb got a corrupt reply, so send it again
+                    self.buffer.remove(0);
+                    out.write_all(&self.last_packet)?;
+                }
+                Some(0x03) => {
+                    self.buffer.remove(0);
+
+                    if self.state == TargetState::Running {
+                        self.state = TargetState::Stopped;
+                        self.send_packet(out, &format!("S{:02x}", SIGINT))?;
+                    }
+                }
+                Some(b'$') => {
+                    let end = match self.buffer.iter().position(|&b| b == b'#') {
+                        Some(end) if end + 2 < self.buffer.len() => end,
+                        _ => return Ok(None)
+                    };
+                    let packet: Vec<u8> = self.buffer.drain(..end + 3).collect();
+                    let data = &packet[1..end];
+                    let checksum = std::str::from_utf8(&packet[end + 1..])
+                        .ok()
+                        .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+                    if !self.no_ack {
+                        let valid = checksum == Some(data.iter().fold(0u8, |sum, &b| sum.wrapping_add(b)));
+
+                        out.write_all(if valid { b"+" } else { b"-" })?;
+
+                        if !valid {
+                            continue;
+                        }
+                    }
+
+                    return Ok(Some(String::from_utf8_lossy(data).into_owned()));
+                }
+                Some(_) => {
+                    // noise between packets
+                    self.buffer.remove(0);
+                }
+            }
+        }
+    }
+
+    fn handle_packet<W: Write>(&mut self, out: &mut W, packet: &str, cpu: &mut Cpu, debugger: &mut Debugger) -> io::Result<()> {
+        let (command, args) = packet.split_at(packet.chars().next().map_or(0, char::len_utf8));
+
+        let reply = match command {
+            "?" => format!("S{:02x}", SIGTRAP),
+            "g" => read_registers(cpu),
+            "G" => reply_result(write_registers(cpu, args)),
+            "p" => match parse_hex(args).and_then(|register| read_register(cpu, register)) {
+                Some(value) => value,
+                None => String::from("E01")
+            },
+            "P" => reply_result(args.split_once('=').and_then(|(register, value)| {
+                write_register(cpu, parse_hex(register)?, value)
+            })),
+            "m" => match read_memory(cpu, args) {
+                Some(bytes) => bytes,
+                None => String::from("E01")
+            },
+            "M" => reply_result(write_memory(cpu, args)),
+            "Z" => reply_result(set_break(debugger, args, true, cpu.get_memory().len())),
+            "z" => reply_result(set_break(debugger, args, false, cpu.get_memory().len())),
+            "s" | "c" => {
+                if !args.is_empty() {
+                    match parse_hex(args) {
+                        Some(addr) => set_program_counter(cpu, addr),
+                        None => return self.send_packet(out, "E01")
+                    }
+                }
+
+                if command == "c" {
+                    // the stop reply is sent once something breaks
+                    self.state = TargetState::Running;
+                    return Ok(());
+                }
+
+                let reason = debugger.step(cpu);
+
+                stop_reply(cpu, &reason)
+            }
+            "k" => {
+                self.state = TargetState::Killed;
+                self.detached = true;
+                return Ok(());
+            }
+            "D" => {
+                self.send_packet(out, "OK")?;
+                self.state = TargetState::Running;
+                self.detached = true;
+                return Ok(());
+            }
+            "H" => String::from("OK"),
+            "Q" if packet == "QStartNoAckMode" => {
+                // the OK is still acknowledged, so only stop acking once it's sent
+                self.send_packet(out, "OK")?;
+                self.no_ack = true;
+                return Ok(());
+            }
+            "q" | "v" => handle_query(packet),
+            _ => String::new()
+        };
+
+        self.send_packet(out, &reply)
+    }
+
+    /// Frames and sends a packet, keeping it around in case gdb asks for it again.
+    fn send_packet<W: Write>(&mut self, out: &mut W, data: &str) -> io::Result<()> {
+        let checksum = data.bytes().fold(0u8, |sum, b| sum.wrapping_add(b));
+        let packet = format!("${}#{:02x}", data, checksum).into_bytes();
+
+        out.write_all(&packet)?;
+        self.last_packet = packet;
+
+        Ok(())
+    }
+}
+
+/// Answers the `q` and `v` packets gdb needs to attach to a single-threaded target.
+fn handle_query(packet: &str) -> String {
+    if packet.starts_with("qSupported") {
+        format!("PacketSize={:x};qXfer:features:read+;QStartNoAckMode+", PACKET_SIZE)
+    } else if let Some(args) = packet.strip_prefix("qXfer:features:read:target.xml:") {
+        read_target_description(args)
+    } else {
+        match packet {
+            "qAttached" => String::from("1"),
+            "qC" => String::from("QC1"),
+            "qfThreadInfo" => String::from("m1"),
+            "qsThreadInfo" => String::from("l"),
+            _ => String::new()
+        }
+    }
+}
+
+/// Builds the stop reply packet for a stop reason.
+fn stop_reply(cpu: &Cpu, reason: &StopReason) -> String {
+    match reason {
+        StopReason::Watchpoint { address, access } => {
+            let kind = match access {
+                Access::Read => "rwatch",
+                Access::Write => "watch"
+            };
+
+            format!("T{:02x}{}:{:x};", SIGTRAP, kind, address)
+        }
+        StopReason::Fault(fault) => {
+            let signal = match fault.kind {
+                FaultKind::UnknownOpcode | FaultKind::MachineCodeCall => SIGILL,
+                _ => SIGSEGV
+            };
+
+            format!("S{:02x}", signal)
+        }
+        StopReason::Exited => String::from("W00"),
+        _ => {
+            // gdb expects the PC to be reported with breaks so it doesn't have to ask for it
+            let pc = read_register(cpu, PC_REGISTER).unwrap_or_default();
+
+            format!("T{:02x}{:02x}:{};", SIGTRAP, PC_REGISTER, pc)
+        }
+    }
+}
+
+fn reply_result(result: Option<()>) -> String {
+    match result {
+        Some(()) => String::from("OK"),
+        None => String::from("E01")
+    }
+}
+
+fn parse_hex(text: &str) -> Option<usize> {
+    usize::from_str_radix(text, 16).ok()
+}
+
+/// Decodes a string of hex byte pairs.
+fn parse_hex_bytes(text: &str) -> Option<Vec<u8>> {
+    if text.len() % 2 != 0 {
+        return None;
+    }
+
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Moves the PC, which also clears a halt so gdb can skip past a fault.
+fn set_program_counter(cpu: &mut Cpu, addr: usize) {
+    cpu.set_program_counter(addr);
+    cpu.clear_halt_fault();
+}
+
+fn get_register_value(cpu: &Cpu, register: usize) -> Option<u16> {
+    let value = match register {
+        0..=15 => cpu.get_registers()[register] as u16,
+        16 => cpu.get_index(),
+        17 => cpu.get_program_counter() as u16,
+        18 => cpu.get_stack().len() as u16,
+        19 => cpu.get_delay_timer() as u16,
+        20 => cpu.get_sound_timer() as u16,
+        _ => return None
+    };
+
+    Some(value)
+}
+
+fn read_register(cpu: &Cpu, register: usize) -> Option<String> {
+    let value = get_register_value(cpu, register)?;
+
+    match REGISTERS[register].1 {
+        1 => Some(format!("{:02x}", value)),
+        _ => Some(format!("{:02x}{:02x}", value & 0xFF, value >> 8))
+    }
+}
+
+fn read_registers(cpu: &Cpu) -> String {
+    (0..REGISTERS.len())
+        .filter_map(|register| read_register(cpu, register))
+        .collect()
+}
+
+fn write_register(cpu: &mut Cpu, register: usize, hex: &str) -> Option<()> {
+    let size = REGISTERS.get(register)?.1;
+    let bytes = parse_hex_bytes(hex).filter(|bytes| bytes.len() == size)?;
+    let value = bytes.iter().rev().fold(0, |value, &b| (value << 8) | b as u16);
+
+    match register {
+        0..=15 => cpu.set_register(register as u8, value as u8),
+        16 => cpu.set_index(value),
+        17 => set_program_counter(cpu, value as usize),
+        18 => cpu.set_stack_pointer(value as usize),
+        19 => cpu.set_delay_timer(value as u8),
+        _ => cpu.set_sound_timer(value as u8)
+    }
+
+    Some(())
+}
+
+fn write_registers(cpu: &mut Cpu, hex: &str) -> Option<()> {
+    let mut pos = 0;
+
+    for (register, &(_, size)) in REGISTERS.iter().enumerate() {
+        let value = hex.get(pos..pos + size * 2)?;
+
+        // gdb sends x's for registers it doesn't know the value of
+        if !value.contains('x') {
+            write_register(cpu, register, value)?;
+        }
+
+        pos += size * 2;
+    }
+
+    Some(())
+}
+
+/// Handles `addr,len`, returning the bytes as hex.
+fn read_memory(cpu: &Cpu, args: &str) -> Option<String> {
+    let (addr, len) = args.split_once(',')?;
+    let (addr, len) = (parse_hex(addr)?, parse_hex(len)?);
+    let memory = cpu.get_memory();
+    let end = addr.checked_add(len)?.min(memory.len());
+
+    if addr >= memory.len() {
+        return None;
+    }
+
+    Some(memory[addr..end].iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Handles `addr,len:bytes`.
+fn write_memory(cpu: &mut Cpu, args: &str) -> Option<()> {
+    let (range, data) = args.split_once(':')?;
+    let (addr, len) = range.split_once(',')?;
+    let (addr, len) = (parse_hex(addr)?, parse_hex(len)?);
+    let bytes = parse_hex_bytes(data).filter(|bytes| bytes.len() == len)?;
+    let memory = cpu.get_memory_mut();
+
+    memory.get_mut(addr..addr.checked_add(len)?)?.copy_from_slice(&bytes);
+
+    Some(())
+}
+
+/// Handles `type,addr,kind` for the `Z` and `z` packets. Types 0 and 1 are breakpoints, and 2, 3
+/// and 4 are write, read and access watchpoints covering `kind` bytes, which have to fit in
+/// `memory_size` bytes of memory.
+fn set_break(debugger: &mut Debugger, args: &str, insert: bool, memory_size: usize) -> Option<()> {
+    let mut parts = args.split(',');
+    let kind = parts.next()?;
+    let addr = parse_hex(parts.next()?)?;
+    let len = parse_hex(parts.next()?.split(';').next()?)?;
+
+    let watch_kind = match kind {
+        "0" | "1" => {
+            if insert {
+                debugger.add_breakpoint(addr);
+            } else {
+                debugger.remove_breakpoint(addr);
+            }
+
+            return Some(());
+        }
+        "2" => WatchKind::Write,
+        "3" => WatchKind::Read,
+        "4" => WatchKind::ReadWrite,
+        _ => return None
+    };
+    let end = addr.checked_add(len.max(1)).filter(|&end| end <= memory_size)?;
+    let watchpoint = Watchpoint {
+        range: addr..=end - 1,
+        kind: watch_kind
+    };
+
+    if insert {
+        debugger.add_watchpoint(watchpoint);
+    } else {
+        debugger.remove_watchpoint(&watchpoint);
+    }
+
+    Some(())
+}
+
+/// Handles `offset,length` for reading the target description, which tells gdb the register layout.
+fn read_target_description(args: &str) -> String {
+    let (offset, len) = match args.split_once(',').and_then(|(o, l)| Some((parse_hex(o)?, parse_hex(l)?))) {
+        Some(range) => range,
+        None => return String::from("E01")
+    };
+
+    let mut xml = String::from(
+        "<?xml version=\"1.0\"?><!DOCTYPE target SYSTEM \"gdb-target.dtd\"><target version=\"1.0\"><feature name=\"org.ch8-rs.chip8\">"
+    );
+
+    for &(name, size) in REGISTERS.iter() {
+        let kind = match name {
+            "pc" => "code_ptr",
+            "i" => "data_ptr",
+            _ => "uint8"
+        };
+
+        xml.push_str(&format!("<reg name=\"{}\" bitsize=\"{}\" type=\"{}\"/>", name, size * 8, kind));
+    }
+
+    xml.push_str("</feature></target>");
+
+    let start = offset.min(xml.len());
+    let end = offset.saturating_add(len).min(xml.len());
+    let marker = if end == xml.len() { 'l' } else { 'm' };
+
+    format!("{}{}", marker, &xml[start..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A session with a CPU that sets V0 and loops, fed bytes in memory instead of over a socket.
+    struct Target {
+        session: Session,
+        cpu: Cpu,
+        debugger: Debugger
+    }
+
+    impl Target {
+        fn new() -> Self {
+            Target {
+                session: Session::new(),
+                cpu: Cpu::with_program(&[0x60, 0x05, 0x12, 0x02]),
+                debugger: Debugger::new()
+            }
+        }
+
+        /// Feeds bytes in as if gdb sent them, returning everything written back.
+        fn feed(&mut self, input: &[u8]) -> String {
+            let mut out = Vec::new();
+
+            self.session.buffer.extend_from_slice(input);
+            self.session.handle_packets(&mut out, &mut self.cpu, &mut self.debugger).unwrap();
+
+            String::from_utf8(out).unwrap()
+        }
+
+        /// Sends a packet and returns the data of the reply, checking it was acked and framed.
+        fn request(&mut self, data: &str) -> String {
+            let output = self.feed(frame(data).as_bytes());
+            let reply = output.strip_prefix('+').expect("packet wasn't acked");
+            let (body, checksum) = reply.strip_prefix('$').and_then(|reply| reply.rsplit_once('#')).expect("bad framing");
+
+            assert_eq!(frame(body), reply, "bad checksum {}", checksum);
+
+            body.to_string()
+        }
+    }
+
+    fn frame(data: &str) -> String {
+        format!("${}#{:02x}", data, data.bytes().fold(0u8, |sum, b| sum.wrapping_add(b)))
+    }
+
+    #[test]
+    fn packets_are_checked_and_acked() {
+        let mut target = Target::new();
+
+        assert_eq!(target.feed(b"$?#3f"), "+$S05#b8");
+
+        // a bad or unreadable checksum is nak'd and the packet dropped
+        assert_eq!(target.feed(b"$?#00"), "-");
+        assert_eq!(target.feed(b"$?#zz"), "-");
+
+        // a nak from gdb resends the last reply
+        assert_eq!(target.feed(b"-"), "$S05#b8");
+
+        // acks and noise between packets are skipped, and split packets wait for the rest
+        assert_eq!(target.feed(b"+x$?#3"), "");
+        assert_eq!(target.feed(b"f"), "+$S05#b8");
+    }
+
+    #[test]
+    fn no_ack_mode_stops_acks_after_its_reply() {
+        let mut target = Target::new();
+
+        assert_eq!(target.request("QStartNoAckMode"), "OK");
+        assert_eq!(target.feed(frame("?").as_bytes()), "$S05#b8");
+    }
+
+    #[test]
+    fn interrupt_stops_a_running_target() {
+        let mut target = Target::new();
+
+        // nothing to interrupt while stopped
+        assert_eq!(target.feed(&[0x03]), "");
+        assert_eq!(target.feed(frame("c").as_bytes()), "+");
+        assert_eq!(target.session.state, TargetState::Running);
+        assert_eq!(target.feed(&[0x03]), "$S02#b5");
+        assert_eq!(target.session.state, TargetState::Stopped);
+    }
+
+    #[test]
+    fn detaching_drops_the_rest_of_the_buffer() {
+        let mut target = Target::new();
+        let input = frame("D") + &frame("?");
+
+        assert_eq!(target.feed(input.as_bytes()), "+$OK#9a");
+        assert!(target.session.detached);
+        assert_eq!(target.session.state, TargetState::Running);
+    }
+
+    #[test]
+    fn registers_are_little_endian() {
+        let mut target = Target::new();
+
+        for register in 0..16 {
+            target.cpu.set_register(register, register * 0x11);
+        }
+
+        target.cpu.set_index(0x1234);
+        target.cpu.set_delay_timer(3);
+        target.cpu.set_sound_timer(4);
+
+        assert_eq!(target.request("g"), "00112233445566778899aabbccddeeff34120002000304");
+        assert_eq!(target.request("p10"), "3412");
+        assert_eq!(target.request("p11"), "0002");
+        assert_eq!(target.request("p15"), "E01");
+        assert_eq!(target.request("pzz"), "E01");
+
+        assert_eq!(target.request("P11=0403"), "OK");
+        assert_eq!(target.cpu.get_program_counter(), 0x304);
+        assert_eq!(target.request("P10=12"), "E01");
+        assert_eq!(target.request("P10"), "E01");
+
+        // gdb sends x's for registers it leaves alone
+        assert_eq!(target.request("Gffffffffffffffffffffffffffffffffcdab0402xx0506"), "OK");
+        assert_eq!(target.cpu.get_registers()[0], 0xFF);
+        assert_eq!(target.cpu.get_index(), 0xABCD);
+        assert_eq!(target.cpu.get_program_counter(), 0x204);
+        assert_eq!(target.cpu.get_delay_timer(), 5);
+        assert_eq!(target.cpu.get_sound_timer(), 6);
+        assert_eq!(target.request("Gff"), "E01");
+    }
+
+    #[test]
+    fn memory_access_is_bounded() {
+        let mut target = Target::new();
+
+        assert_eq!(target.request("m200,4"), "60051202");
+        // reads running off the end are cut short, and ones starting past it fail
+        assert_eq!(target.request("mffe,4"), "0000");
+        assert_eq!(target.request("m1000,1"), "E01");
+        assert_eq!(target.request("m200"), "E01");
+        assert_eq!(target.request("mzz,1"), "E01");
+
+        assert_eq!(target.request("M300,2:abcd"), "OK");
+        assert_eq!(&target.cpu.get_memory()[0x300..0x302], &[0xAB, 0xCD]);
+        assert_eq!(target.request("M300,2:abc"), "E01");
+        assert_eq!(target.request("M300,3:abcd"), "E01");
+        assert_eq!(target.request("M300,2:zzzz"), "E01");
+        assert_eq!(target.request("Mfff,2:abcd"), "E01");
+    }
+
+    #[test]
+    fn breakpoints_and_watchpoints_are_set_and_removed() {
+        let mut target = Target::new();
+
+        assert_eq!(target.request("Z0,202,2"), "OK");
+        assert!(target.debugger.has_breakpoint(0x202));
+        assert_eq!(target.request("z0,202,2"), "OK");
+        assert!(!target.debugger.has_breakpoint(0x202));
+
+        assert_eq!(target.request("Z2,300,4"), "OK");
+        assert_eq!(target.request("Z3,fff,1"), "OK");
+        assert_eq!(target.debugger.get_watchpoints(), &[
+            Watchpoint {
+                range: 0x300..=0x303,
+                kind: WatchKind::Write
+            },
+            Watchpoint {
+                range: 0xFFF..=0xFFF,
+                kind: WatchKind::Read
+            }
+        ]);
+
+        assert_eq!(target.request("z2,300,4"), "OK");
+        assert_eq!(target.debugger.get_watchpoints().len(), 1);
+    }
+
+    #[test]
+    fn watchpoints_past_memory_are_refused() {
+        let mut target = Target::new();
+
+        assert_eq!(target.request("Z2,fff,2"), "E01");
+        assert_eq!(target.request("Z2,fffff,1"), "E01");
+        assert_eq!(target.request("Z4,ffffffffffffffff,2"), "E01");
+        assert_eq!(target.request("Z9,200,1"), "E01");
+        assert_eq!(target.request("Z2,300"), "E01");
+        assert!(target.debugger.get_watchpoints().is_empty());
+
+        // the end of memory moves with its size
+        target.cpu.set_memory_size(0x10000);
+
+        assert_eq!(target.request("Z2,fffff,1"), "E01");
+        assert_eq!(target.request("Z2,ffff,1"), "OK");
+    }
+
+    #[test]
+    fn hex_bytes_are_decoded_in_pairs() {
+        assert_eq!(parse_hex_bytes("0aFF"), Some(vec![0x0A, 0xFF]));
+        assert_eq!(parse_hex_bytes(""), Some(vec![]));
+        assert_eq!(parse_hex_bytes("0"), None);
+        assert_eq!(parse_hex_bytes("0g"), None);
+        assert_eq!(parse_hex_bytes("0é0"), None);
+    }
+}
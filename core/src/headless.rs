@@ -0,0 +1,225 @@
+use crate::cpu::Cpu;
+use crate::debugger::{Condition, Debugger, StopReason};
+use crate::display::DisplayBuffer;
+
+use std::error::Error;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+/// Frames a `tap` in an input script holds the key down for.
+pub const TAP_FRAMES: u32 = 3;
+
+/// A key changing state at the start of a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub frame: u32,
+    pub key: u8,
+    pub pressed: bool
+}
+
+/// Key presses and releases to feed a program as it runs, one per line:
+///
+/// ```text
+/// # frame action key
+/// 60 down 5
+/// 90 up 5
+/// 120 tap A
+/// ```
+///
+/// Keys are hex. `tap` presses the key and releases it [`TAP_FRAMES`] later. Blank lines and
+/// anything after a `#` are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InputScript {
+    events: Vec<KeyEvent>
+}
+
+/// Why an input script could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptError {
+    /// Line number, starting at 1.
+    pub line: usize,
+    pub message: String
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for ScriptError {}
+
+impl InputScript {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ScriptError> {
+        let mut script = InputScript::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let error = |message: &str| ScriptError {
+                line: i + 1,
+                message: message.to_string()
+            };
+            let line = line.split('#').next().unwrap_or("").trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let (frame, action, key) = match parts.as_slice() {
+                [frame, action, key] => (*frame, *action, *key),
+                _ => return Err(error("expected a frame, an action and a key"))
+            };
+            let frame = frame.parse().map_err(|_| error("frame must be a number"))?;
+            let key = u8::from_str_radix(key, 16)
+                .ok()
+                .filter(|&key| key <= 0xF)
+                .ok_or_else(|| error("key must be a hex digit 0-F"))?;
+
+            match action {
+                "down" => script.push(frame, key, true),
+                "up" => script.push(frame, key, false),
+                "tap" => {
+                    let release = frame
+                        .checked_add(TAP_FRAMES)
+                        .ok_or_else(|| error("tap is too late to be released"))?;
+
+                    script.push(frame, key, true);
+                    script.push(release, key, false);
+                }
+                _ => return Err(error("action must be down, up or tap"))
+            }
+        }
+
+        Ok(script)
+    }
+
+    /// Adds an event, keeping events in frame order.
+    pub fn push(&mut self, frame: u32, key: u8, pressed: bool) {
+        let index = self.events.partition_point(|event| event.frame <= frame);
+
+        self.events.insert(index, KeyEvent {
+            frame,
+            key,
+            pressed
+        });
+    }
+
+    pub fn get_events(&self) -> &[KeyEvent] {
+        &self.events
+    }
+
+    /// Applies the events for a frame to the CPU's keyboard.
+    pub fn apply(&self, frame: u32, cpu: &mut Cpu) {
+        for event in self.events.iter().filter(|event| event.frame == frame) {
+            cpu.get_keyboard().set_key_pressed(event.key, event.pressed);
+        }
+    }
+}
+
+/// How long to run a program for.
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    /// Frames to run before stopping.
+    pub frames: u32,
+    pub input: InputScript,
+    /// Stop before executing an instruction at one of these addresses.
+    pub stop_addresses: Vec<usize>,
+    /// Stop as soon as one of these conditions becomes true.
+    pub stop_conditions: Vec<Condition>
+}
+
+/// How a run ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunResult {
+    /// Frames started, including one that was cut short by a stop.
+    pub frames: u32,
+    /// Why the run stopped early, or `None` if it ran for every frame.
+    pub stop: Option<StopReason>
+}
+
+/// Runs a program without any display or audio, frame by frame, stopping after the frame count
+/// or when the program exits, faults, reaches a stop address or meets a stop condition.
+pub fn run(cpu: &mut Cpu, options: &RunOptions) -> RunResult {
+    let mut debugger = Debugger::new();
+
+    for &addr in options.stop_addresses.iter() {
+        debugger.add_breakpoint(addr);
+    }
+
+    for &condition in options.stop_conditions.iter() {
+        debugger.add_condition(condition);
+    }
+
+    for frame in 0..options.frames {
+        options.input.apply(frame, cpu);
+
+        if let Some(stop) = debugger.run_frame(cpu) {
+            return RunResult {
+                frames: frame + 1,
+                stop: Some(stop)
+            };
+        }
+    }
+
+    RunResult {
+        frames: options.frames,
+        stop: None
+    }
+}
+
+/// Draws the display as text, `#` for pixels that are set and `.` for ones that aren't.
+pub fn format_display(display: &DisplayBuffer) -> String {
+    let mut text = String::new();
+
+    for y in 0..display.get_height() {
+        for x in 0..display.get_width() {
+            text.push(if display.is_set_at(x, y) { '#' } else { '.' });
+        }
+
+        text.push('\n');
+    }
+
+    text
+}
+
+/// Lists the registers, timers and stack.
+pub fn format_registers(cpu: &Cpu) -> String {
+    let registers: Vec<String> = cpu.get_registers()
+        .iter()
+        .enumerate()
+        .map(|(i, val)| format!("V{:X}={:02X}", i, val))
+        .collect();
+    let stack: Vec<String> = cpu.get_stack().iter().map(|addr| format!("{:03X}", addr)).collect();
+
+    format!(
+        "{}\nPC={:03X} I={:03X} DT={:02X} ST={:02X}\nStack: [{}]\n",
+        registers.join(" "),
+        cpu.get_program_counter(),
+        cpu.get_index(),
+        cpu.get_delay_timer(),
+        cpu.get_sound_timer(),
+        stack.join(" ")
+    )
+}
+
+/// Dumps a range of memory as hex, 16 bytes to a line.
+pub fn format_memory(cpu: &Cpu, range: RangeInclusive<usize>) -> String {
+    let memory = cpu.get_memory();
+    let end = (*range.end()).min(memory.len().saturating_sub(1));
+    let mut text = String::new();
+
+    for row in (*range.start()..=end).step_by(16) {
+        let bytes: Vec<String> = memory[row..=end.min(row + 15)]
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect();
+
+        text.push_str(&format!("{:03X}: {}\n", row, bytes.join(" ")));
+    }
+
+    text
+}
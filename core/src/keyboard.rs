@@ -0,0 +1,84 @@
+use crate::savestate::{SaveStateError, StateReader, StateWriter};
+
+/// State of the 16-key CHIP-8 hex keypad.
+pub struct KeyboardInput {
+    key_states: [bool; 16],
+    last_released_key: Option<u8>,
+    track_next_released_key: bool
+}
+
+impl KeyboardInput {
+    pub fn new() -> Self {
+        KeyboardInput {
+            key_states: [false; 16],
+            last_released_key: None,
+            track_next_released_key: false
+        }
+    }
+
+    /// Checks if we're tracking the next key release
+    pub fn is_tracking_next_key_release(&self) -> bool {
+        self.track_next_released_key
+    }
+
+    /// Tracks for the next key
+    pub fn track_next_key_release(&mut self, track: bool) {
+        self.last_released_key = None;
+        self.track_next_released_key = track;
+    }
+
+    /// Gets the last released key if we're waiting for it
+    pub fn get_last_released_key(&self) -> Option<u8> {
+        self.last_released_key
+    }
+
+    /// Sets a key state. Keys above F are ignored.
+    pub fn set_key_pressed(&mut self, key: u8, pressed: bool) {
+        match self.key_states.get_mut(key as usize) {
+            Some(state) => *state = pressed,
+            None => return
+        }
+
+        if !pressed && self.track_next_released_key {
+            self.last_released_key = Some(key);
+        }
+    }
+
+    /// Checks if a key is currently pressed. Keys above F are never pressed.
+    pub fn is_key_pressed(&self, key: u8) -> bool {
+        self.key_states.get(key as usize).copied().unwrap_or(false)
+    }
+
+    /// Writes the key states and wait-for-key state to a save state.
+    pub(crate) fn write_state(&self, writer: &mut StateWriter) {
+        writer.write_bits(&self.key_states);
+        writer.write_bool(self.track_next_released_key);
+        writer.write_bool(self.last_released_key.is_some());
+        writer.write_u8(self.last_released_key.unwrap_or(0));
+    }
+
+    /// Reads a keyboard written by [`KeyboardInput::write_state`].
+    pub(crate) fn read_state(reader: &mut StateReader) -> Result<Self, SaveStateError> {
+        let mut keyboard = KeyboardInput::new();
+
+        reader.read_bits(&mut keyboard.key_states)?;
+        keyboard.track_next_released_key = reader.read_bool()?;
+
+        let has_released_key = reader.read_bool()?;
+        let released_key = reader.read_u8()?;
+
+        if released_key > 0xF {
+            return Err(SaveStateError::Invalid("released key"));
+        }
+
+        keyboard.last_released_key = if has_released_key { Some(released_key) } else { None };
+
+        Ok(keyboard)
+    }
+}
+
+impl Default for KeyboardInput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,39 @@
+//! Platform-independent CHIP-8 interpreter core.
+//!
+//! This crate contains the CPU, display buffer, keyboard state and disassembler
+//! without any windowing or audio dependencies, so it can be embedded in
+//! frontends, test rigs and tooling.
+//!
+//! ```no_run
+//! use ch8_core::cpu::Cpu;
+//!
+//! let rom = std::fs::read("pong.rom").unwrap();
+//! let mut cpu = Cpu::new();
+//!
+//! cpu.init();
+//! cpu.load_program(&rom).expect("ROM doesn't fit in memory");
+//!
+//! // advance exactly one second of emulated time
+//! for _ in 0..60 {
+//!     cpu.run_frame().expect("ROM faulted");
+//! }
+//! ```
+
+pub mod audio;
+pub mod conformance;
+pub mod cpu;
+pub mod database;
+pub mod debugger;
+pub mod disassembler;
+pub mod display;
+pub mod fault;
+pub mod gdb;
+pub mod headless;
+pub mod keyboard;
+pub mod quirks;
+pub mod rewind;
+pub mod savestate;
+pub mod screenshot;
+pub mod sha1;
+pub mod trace;
+pub mod wav;
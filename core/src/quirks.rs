@@ -0,0 +1,134 @@
+// quirk descriptions here: https://github.com/Timendus/chip8-test-suite#quirks-test
+
+use crate::cpu::{MEMORY_SIZE, XO_CHIP_MEMORY_SIZE};
+
+/// Names accepted by [`Quirks::from_preset_name`].
+pub const PRESET_NAMES: [&str; 4] = ["vip", "chip48", "schip", "modern"];
+
+/// Names accepted by [`Quirks::set_by_name`], the same as the on/off fields.
+pub const QUIRK_NAMES: [&str; 6] = [
+    "shift_uses_vy",
+    "load_store_increments_index",
+    "jump_uses_vx",
+    "logic_resets_vf",
+    "clip_sprites",
+    "display_wait"
+];
+
+/// Behavioral differences between CHIP-8 platforms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quirks {
+    /// `8XY6`/`8XYE` shift VY and store the result in VX, instead of shifting VX in place.
+    pub shift_uses_vy: bool,
+    /// `FX55`/`FX65` leave the index register pointing past the last register transferred.
+    pub load_store_increments_index: bool,
+    /// `BXNN` jumps to `XNN` plus VX, instead of `NNN` plus V0.
+    pub jump_uses_vx: bool,
+    /// `8XY1`/`8XY2`/`8XY3` reset VF to 0.
+    pub logic_resets_vf: bool,
+    /// Sprites are clipped at the edges of the screen instead of wrapping around.
+    pub clip_sprites: bool,
+    /// `DXYN` waits for the next frame before execution continues, limiting drawing to once per frame.
+    pub display_wait: bool,
+    /// Size of the address space, [`MEMORY_SIZE`] or [`XO_CHIP_MEMORY_SIZE`] for XO-CHIP programs.
+    pub memory_size: usize
+}
+
+impl Quirks {
+    /// The original CHIP-8 interpreter on the COSMAC VIP.
+    pub fn cosmac_vip() -> Self {
+        Quirks {
+            shift_uses_vy: true,
+            load_store_increments_index: true,
+            jump_uses_vx: false,
+            logic_resets_vf: true,
+            clip_sprites: true,
+            display_wait: true,
+            memory_size: MEMORY_SIZE
+        }
+    }
+
+    /// CHIP-48 on the HP-48 graphing calculators.
+    pub fn chip48() -> Self {
+        Quirks {
+            shift_uses_vy: false,
+            load_store_increments_index: true,
+            jump_uses_vx: true,
+            logic_resets_vf: false,
+            clip_sprites: true,
+            display_wait: false,
+            memory_size: MEMORY_SIZE
+        }
+    }
+
+    /// SUPER-CHIP 1.1 on the HP-48 graphing calculators.
+    pub fn super_chip() -> Self {
+        Quirks {
+            shift_uses_vy: false,
+            load_store_increments_index: false,
+            jump_uses_vx: true,
+            logic_resets_vf: false,
+            clip_sprites: true,
+            display_wait: false,
+            memory_size: MEMORY_SIZE
+        }
+    }
+
+    /// Modern interpreters such as Octo, as targeted by XO-CHIP programs, with 64K of memory.
+    pub fn modern() -> Self {
+        Quirks {
+            shift_uses_vy: true,
+            load_store_increments_index: true,
+            jump_uses_vx: false,
+            logic_resets_vf: false,
+            clip_sprites: false,
+            display_wait: false,
+            memory_size: XO_CHIP_MEMORY_SIZE
+        }
+    }
+
+    /// Looks up a preset by one of the names in [`PRESET_NAMES`].
+    pub fn from_preset_name(name: &str) -> Option<Self> {
+        match name {
+            "vip" => Some(Quirks::cosmac_vip()),
+            "chip48" => Some(Quirks::chip48()),
+            "schip" => Some(Quirks::super_chip()),
+            "modern" => Some(Quirks::modern()),
+            _ => None
+        }
+    }
+
+    /// Turns a quirk on or off by one of the names in [`QUIRK_NAMES`]. Returns false if there's
+    /// no quirk with that name.
+    pub fn set_by_name(&mut self, name: &str, enabled: bool) -> bool {
+        let quirk = match name {
+            "shift_uses_vy" => &mut self.shift_uses_vy,
+            "load_store_increments_index" => &mut self.load_store_increments_index,
+            "jump_uses_vx" => &mut self.jump_uses_vx,
+            "logic_resets_vf" => &mut self.logic_resets_vf,
+            "clip_sprites" => &mut self.clip_sprites,
+            "display_wait" => &mut self.display_wait,
+            _ => return false
+        };
+
+        *quirk = enabled;
+
+        true
+    }
+}
+
+impl Default for Quirks {
+    /// No quirks, which is how this interpreter has always run ROMs without a preset: shifts work
+    /// on VX in place, `BNNN` adds V0, the index register is left alone and sprites wrap around.
+    fn default() -> Self {
+        Quirks {
+            shift_uses_vy: false,
+            load_store_increments_index: false,
+            jump_uses_vx: false,
+            logic_resets_vf: false,
+            clip_sprites: false,
+            display_wait: false,
+            memory_size: MEMORY_SIZE
+        }
+    }
+}
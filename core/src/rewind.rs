@@ -0,0 +1,152 @@
+use std::collections::VecDeque;
+
+/// Bounded history of save states for stepping backwards through time.
+///
+/// Only the newest state is kept in full. Every older state is stored as the difference from the
+/// state after it, XORed and run-length encoded, so frames where little changed take a few bytes.
+pub struct RewindBuffer {
+    capacity: usize,
+    latest: Option<Vec<u8>>,
+    deltas: VecDeque<Delta>
+}
+
+/// How to get from a state back to the one before it.
+enum Delta {
+    /// Run-length encoded XOR of two states of the same length.
+    Xor(Vec<u8>),
+    /// The previous state in full, when the lengths differ.
+    Full(Vec<u8>)
+}
+
+impl RewindBuffer {
+    /// Creates a buffer holding at most `capacity` states.
+    pub fn new(capacity: usize) -> Self {
+        RewindBuffer {
+            capacity: capacity.max(1),
+            latest: None,
+            deltas: VecDeque::new()
+        }
+    }
+
+    /// Records a new state, dropping the oldest one if the buffer is full.
+    pub fn push(&mut self, state: Vec<u8>) {
+        if let Some(latest) = self.latest.take() {
+            self.deltas.push_back(Delta::between(&state, latest));
+
+            if self.deltas.len() >= self.capacity {
+                self.deltas.pop_front();
+            }
+        }
+
+        self.latest = Some(state);
+    }
+
+    /// Removes and returns the newest state.
+    pub fn pop(&mut self) -> Option<Vec<u8>> {
+        let latest = self.latest.take()?;
+
+        self.latest = self.deltas.pop_back().map(|delta| delta.apply(&latest));
+
+        Some(latest)
+    }
+
+    /// Gets the number of states held.
+    pub fn len(&self) -> usize {
+        self.latest.is_some() as usize + self.deltas.len()
+    }
+
+    /// Checks if there are no states to rewind to.
+    pub fn is_empty(&self) -> bool {
+        self.latest.is_none()
+    }
+
+    /// Drops every state.
+    pub fn clear(&mut self) {
+        self.latest = None;
+        self.deltas.clear();
+    }
+
+    /// Gets the approximate number of bytes used by the stored states.
+    pub fn get_memory_usage(&self) -> usize {
+        let latest = self.latest.as_ref().map_or(0, Vec::len);
+        let deltas: usize = self.deltas
+            .iter()
+            .map(|delta| match delta {
+                Delta::Xor(bytes) | Delta::Full(bytes) => bytes.len()
+            })
+            .sum();
+
+        latest + deltas
+    }
+}
+
+impl Delta {
+    /// Creates a delta that turns `next` back into `previous`.
+    fn between(next: &[u8], previous: Vec<u8>) -> Self {
+        if next.len() != previous.len() {
+            return Delta::Full(previous);
+        }
+
+        let xor: Vec<u8> = next.iter().zip(previous.iter()).map(|(a, b)| a ^ b).collect();
+
+        Delta::Xor(encode_runs(&xor))
+    }
+
+    /// Recreates the previous state from the state after it.
+    fn apply(self, next: &[u8]) -> Vec<u8> {
+        match self {
+            Delta::Xor(runs) => {
+                let mut state = next.to_vec();
+                let mut pos = 0;
+
+                for_each_run(&runs, |zeros, literal| {
+                    pos += zeros;
+
+                    for (byte, &xor) in state[pos..pos + literal.len()].iter_mut().zip(literal) {
+                        *byte ^= xor;
+                    }
+
+                    pos += literal.len();
+                });
+
+                state
+            }
+            Delta::Full(state) => state
+        }
+    }
+}
+
+/// Encodes bytes as runs of a zero count followed by a count of literal bytes and the bytes themselves.
+/// Counts are 16-bit big-endian.
+fn encode_runs(bytes: &[u8]) -> Vec<u8> {
+    let mut runs = Vec::new();
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        let zeros = bytes[pos..].iter().take(u16::MAX as usize).take_while(|&&b| b == 0).count();
+        let literal_start = pos + zeros;
+        let literals = bytes[literal_start..].iter().take(u16::MAX as usize).take_while(|&&b| b != 0).count();
+
+        runs.extend_from_slice(&(zeros as u16).to_be_bytes());
+        runs.extend_from_slice(&(literals as u16).to_be_bytes());
+        runs.extend_from_slice(&bytes[literal_start..literal_start + literals]);
+
+        pos = literal_start + literals;
+    }
+
+    runs
+}
+
+/// Calls `f` with the zero count and literal bytes of each run written by [`encode_runs`].
+fn for_each_run<F: FnMut(usize, &[u8])>(runs: &[u8], mut f: F) {
+    let mut pos = 0;
+
+    while pos + 4 <= runs.len() {
+        let zeros = u16::from_be_bytes([runs[pos], runs[pos + 1]]) as usize;
+        let literals = u16::from_be_bytes([runs[pos + 2], runs[pos + 3]]) as usize;
+
+        f(zeros, &runs[pos + 4..pos + 4 + literals]);
+
+        pos += 4 + literals;
+    }
+}
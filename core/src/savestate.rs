@@ -0,0 +1,158 @@
+use std::error::Error;
+use std::fmt;
+
+/// Bytes at the start of every save state.
+pub const MAGIC: [u8; 4] = *b"CH8S";
+
+/// Version of the save state layout written by this build.
+pub const VERSION: u8 = 1;
+
+/// Why a save state could not be loaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveStateError {
+    /// The data does not start with [`MAGIC`].
+    BadMagic,
+    /// The data was written by an incompatible version.
+    UnsupportedVersion(u8),
+    /// The data ends before the state is complete.
+    Truncated,
+    /// A value is out of range for the machine, e.g. a stack pointer past the end of the stack.
+    Invalid(&'static str)
+}
+
+impl fmt::Display for SaveStateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveStateError::BadMagic => write!(f, "not a save state"),
+            SaveStateError::UnsupportedVersion(version) => write!(f, "unsupported save state version {}", version),
+            SaveStateError::Truncated => write!(f, "save state is truncated"),
+            SaveStateError::Invalid(field) => write!(f, "save state has an invalid {}", field)
+        }
+    }
+}
+
+impl Error for SaveStateError {}
+
+/// Appends big-endian values to a save state buffer.
+pub(crate) struct StateWriter {
+    buffer: Vec<u8>
+}
+
+impl StateWriter {
+    /// Creates a writer with the header already written.
+    pub fn new() -> Self {
+        let mut buffer = Vec::new();
+
+        buffer.extend_from_slice(&MAGIC);
+        buffer.push(VERSION);
+
+        StateWriter {
+            buffer
+        }
+    }
+
+    pub fn write_u8(&mut self, val: u8) {
+        self.buffer.push(val);
+    }
+
+    pub fn write_bool(&mut self, val: bool) {
+        self.write_u8(val as u8);
+    }
+
+    pub fn write_u16(&mut self, val: u16) {
+        self.buffer.extend_from_slice(&val.to_be_bytes());
+    }
+
+    pub fn write_u32(&mut self, val: u32) {
+        self.buffer.extend_from_slice(&val.to_be_bytes());
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Writes booleans packed eight to a byte.
+    pub fn write_bits(&mut self, bits: &[bool]) {
+        for chunk in bits.chunks(8) {
+            let byte = chunk
+                .iter()
+                .enumerate()
+                .fold(0, |byte, (i, &bit)| byte | ((bit as u8) << i));
+
+            self.write_u8(byte);
+        }
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.buffer
+    }
+}
+
+/// Reads big-endian values back out of a save state buffer.
+pub(crate) struct StateReader<'a> {
+    buffer: &'a [u8]
+}
+
+impl<'a> StateReader<'a> {
+    /// Creates a reader after checking the header.
+    pub fn new(buffer: &'a [u8]) -> Result<Self, SaveStateError> {
+        let mut reader = StateReader {
+            buffer
+        };
+
+        if reader.read_bytes(MAGIC.len()).map_err(|_| SaveStateError::BadMagic)? != MAGIC {
+            return Err(SaveStateError::BadMagic);
+        }
+
+        let version = reader.read_u8()?;
+
+        if version != VERSION {
+            return Err(SaveStateError::UnsupportedVersion(version));
+        }
+
+        Ok(reader)
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, SaveStateError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool, SaveStateError> {
+        Ok(self.read_u8()? != 0)
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16, SaveStateError> {
+        let bytes = self.read_bytes(2)?;
+
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, SaveStateError> {
+        let bytes = self.read_bytes(4)?;
+
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], SaveStateError> {
+        if self.buffer.len() < len {
+            return Err(SaveStateError::Truncated);
+        }
+
+        let (bytes, rest) = self.buffer.split_at(len);
+
+        self.buffer = rest;
+
+        Ok(bytes)
+    }
+
+    /// Fills a slice from booleans packed eight to a byte.
+    pub fn read_bits(&mut self, bits: &mut [bool]) -> Result<(), SaveStateError> {
+        let bytes = self.read_bytes(bits.len().div_ceil(8))?;
+
+        for (i, bit) in bits.iter_mut().enumerate() {
+            *bit = (bytes[i / 8] >> (i % 8)) & 1 > 0;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,197 @@
+use crate::display::DisplayBuffer;
+
+use std::error::Error;
+use std::fmt;
+
+/// Monochrome copy of the display, stored as a plain-text PBM (`P1`) image so reference
+/// images can be viewed in an image viewer and diffed as text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Screenshot {
+    width: usize,
+    height: usize,
+    pixels: Vec<bool>
+}
+
+/// Why a PBM image could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PbmError {
+    /// The image doesn't start with `P1`.
+    NotPlainPbm,
+    /// The width or height is missing or isn't a number.
+    BadSize,
+    /// A pixel isn't `0` or `1`.
+    BadPixel(String),
+    /// The number of pixels doesn't match the size.
+    WrongPixelCount { expected: usize, found: usize }
+}
+
+impl fmt::Display for PbmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PbmError::NotPlainPbm => write!(f, "not a plain PBM image"),
+            PbmError::BadSize => write!(f, "PBM image has an invalid size"),
+            PbmError::BadPixel(pixel) => write!(f, "PBM image has an invalid pixel {}", pixel),
+            PbmError::WrongPixelCount { expected, found } => {
+                write!(f, "PBM image has {} pixels but should have {}", found, expected)
+            }
+        }
+    }
+}
+
+impl Error for PbmError {}
+
+impl Screenshot {
+    /// Captures the display at its current resolution. A pixel is set if it's set on any plane.
+    pub fn from_display(display: &DisplayBuffer) -> Self {
+        let width = display.get_width() as usize;
+        let height = display.get_height() as usize;
+        let pixels = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| display.is_set_at(x as u8, y as u8))
+            .collect();
+
+        Screenshot {
+            width,
+            height,
+            pixels
+        }
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    pub fn is_set_at(&self, x: usize, y: usize) -> bool {
+        self.pixels[x + y * self.width]
+    }
+
+    /// Parses a plain PBM image. Comments starting with `#` are ignored.
+    pub fn from_pbm(text: &str) -> Result<Self, PbmError> {
+        let mut tokens = text
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or(""))
+            .flat_map(str::split_whitespace);
+
+        if tokens.next() != Some("P1") {
+            return Err(PbmError::NotPlainPbm);
+        }
+
+        let mut size = || tokens.next().and_then(|token| token.parse::<usize>().ok()).ok_or(PbmError::BadSize);
+        let width = size()?;
+        let height = size()?;
+
+        // pixels don't have to be separated by whitespace
+        let pixels = tokens
+            .flat_map(str::chars)
+            .map(|pixel| match pixel {
+                '0' => Ok(false),
+                '1' => Ok(true),
+                _ => Err(PbmError::BadPixel(pixel.to_string()))
+            })
+            .collect::<Result<Vec<bool>, PbmError>>()?;
+
+        if pixels.len() != width * height {
+            return Err(PbmError::WrongPixelCount {
+                expected: width * height,
+                found: pixels.len()
+            });
+        }
+
+        Ok(Screenshot {
+            width,
+            height,
+            pixels
+        })
+    }
+
+    /// Writes the image as plain PBM, one row of pixels to a line.
+    pub fn to_pbm(&self) -> String {
+        let mut text = format!("P1\n{} {}\n", self.width, self.height);
+
+        for row in self.pixels.chunks(self.width) {
+            let row: Vec<&str> = row.iter().map(|&pixel| if pixel { "1" } else { "0" }).collect();
+
+            text.push_str(&row.join(" "));
+            text.push('\n');
+        }
+
+        text
+    }
+
+    /// Compares against a reference image, returning the differences if there are any.
+    pub fn diff(&self, expected: &Screenshot) -> Option<ScreenshotDiff> {
+        if self.width != expected.width || self.height != expected.height {
+            return Some(ScreenshotDiff {
+                actual: self.clone(),
+                expected: expected.clone(),
+                mismatched: self.pixels.len().max(expected.pixels.len())
+            });
+        }
+
+        let mismatched = self.pixels
+            .iter()
+            .zip(expected.pixels.iter())
+            .filter(|(a, b)| a != b)
+            .count();
+
+        if mismatched == 0 {
+            return None;
+        }
+
+        Some(ScreenshotDiff {
+            actual: self.clone(),
+            expected: expected.clone(),
+            mismatched
+        })
+    }
+}
+
+/// Pixels that differ between a screenshot and its reference image.
+///
+/// Displays as a map of the screen where `#` and `.` are pixels that match, `+` are pixels that
+/// are set but shouldn't be, and `-` are pixels that should be set but aren't.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScreenshotDiff {
+    pub actual: Screenshot,
+    pub expected: Screenshot,
+    /// Number of pixels that differ.
+    pub mismatched: usize
+}
+
+impl fmt::Display for ScreenshotDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (actual, expected) = (&self.actual, &self.expected);
+
+        if actual.width != expected.width || actual.height != expected.height {
+            return write!(
+                f,
+                "screenshot is {}x{} but the reference is {}x{}",
+                actual.width,
+                actual.height,
+                expected.width,
+                expected.height
+            );
+        }
+
+        writeln!(f, "{} of {} pixels differ (+ extra, - missing):", self.mismatched, actual.pixels.len())?;
+
+        for y in 0..actual.height {
+            let row: String = (0..actual.width)
+                .map(|x| match (actual.is_set_at(x, y), expected.is_set_at(x, y)) {
+                    (true, true) => '#',
+                    (false, false) => '.',
+                    (true, false) => '+',
+                    (false, true) => '-'
+                })
+                .collect();
+
+            writeln!(f, "{}", row)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,117 @@
+use crate::disassembler::OpCode;
+
+use std::io::{self, Write};
+use std::ops::RangeInclusive;
+
+/// Limits which instructions are written to a trace.
+#[derive(Debug, Clone, Default)]
+pub struct TraceFilter {
+    /// Only trace instructions at these addresses.
+    pub address_range: Option<RangeInclusive<usize>>,
+    /// Only trace these opcode kinds, named like the [`OpCode`] variants, e.g. `"DrawSprite"`.
+    /// `"Unknown"` matches words that don't decode.
+    pub opcode_kinds: Option<Vec<String>>
+}
+
+impl TraceFilter {
+    /// Checks if an instruction passes the filter.
+    pub fn matches(&self, addr: usize, kind: &str) -> bool {
+        let in_range = self.address_range
+            .as_ref()
+            .map_or(true, |range| range.contains(&addr));
+        let is_kind = self.opcode_kinds
+            .as_ref()
+            .map_or(true, |kinds| kinds.iter().any(|k| k == kind));
+
+        in_range && is_kind
+    }
+}
+
+/// Machine state captured just before an instruction executes.
+pub struct TraceEntry<'a> {
+    pub address: usize,
+    pub word: u16,
+    pub opcode: Option<&'a OpCode>,
+    pub registers: &'a [u8; 16],
+    pub index: u16,
+    pub delay_timer: u8,
+    pub sound_timer: u8
+}
+
+/// Writes executed instructions as JSON Lines, one object per instruction:
+///
+/// ```text
+/// {"pc":"0x200","word":"0x00E0","op":"ClearDisplay","args":[],"v":[0,0,...],"i":"0x000","dt":0,"st":0}
+/// {"pc":"0x202","word":"0xD015","op":"DrawSprite","args":[0,1,5],"v":[0,0,...],"i":"0x000","dt":0,"st":0}
+/// ```
+///
+/// `op` is the opcode kind and `args` its operands as numbers, see [`OpCode::get_operands`].
+/// `op` is `null` for words that don't decode. The output is stable so traces from
+/// different versions can be diffed.
+pub struct Tracer {
+    writer: Box<dyn Write>,
+    filter: TraceFilter,
+    error: Option<io::Error>
+}
+
+impl Tracer {
+    pub fn new(writer: Box<dyn Write>, filter: TraceFilter) -> Self {
+        Tracer {
+            writer,
+            filter,
+            error: None
+        }
+    }
+
+    /// Writes an instruction if it passes the filter. After a write fails, nothing more is written.
+    pub fn trace(&mut self, entry: &TraceEntry) {
+        if self.error.is_some() {
+            return;
+        }
+
+        let kind = entry.opcode.map_or("Unknown", OpCode::get_name);
+
+        if !self.filter.matches(entry.address, kind) {
+            return;
+        }
+
+        let op = match entry.opcode {
+            Some(opcode) => format!("\"{}\"", opcode.get_name()),
+            None => String::from("null")
+        };
+        let args: Vec<String> = entry
+            .opcode
+            .map(OpCode::get_operands)
+            .unwrap_or_default()
+            .iter()
+            .map(u16::to_string)
+            .collect();
+        let registers: Vec<String> = entry.registers.iter().map(u8::to_string).collect();
+
+        let result = writeln!(
+            self.writer,
+            "{{\"pc\":\"0x{:03X}\",\"word\":\"0x{:04X}\",\"op\":{},\"args\":[{}],\"v\":[{}],\"i\":\"0x{:03X}\",\"dt\":{},\"st\":{}}}",
+            entry.address,
+            entry.word,
+            op,
+            args.join(","),
+            registers.join(","),
+            entry.index,
+            entry.delay_timer,
+            entry.sound_timer
+        );
+
+        if let Err(err) = result {
+            self.error = Some(err);
+        }
+    }
+
+    /// Flushes the trace, returning the first error that occurred while writing it.
+    pub fn finish(mut self) -> io::Result<()> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+
+        self.writer.flush()
+    }
+}
@@ -0,0 +1,175 @@
+use crate::audio::{self, AudioSink, Beeper, BeeperSettings, Tone};
+
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// Size of the RIFF and format headers before the sample data.
+const HEADER_SIZE: u32 = 44;
+
+const BITS_PER_SAMPLE: u16 = 16;
+
+/// Writes 16-bit mono PCM samples to a WAV file. The sizes in the header are filled in by
+/// [`WavWriter::finish`].
+pub struct WavWriter<W: Write + Seek> {
+    writer: W,
+    sample_rate: u32,
+    samples: u32
+}
+
+impl<W: Write + Seek> WavWriter<W> {
+    pub fn new(mut writer: W, sample_rate: u32) -> io::Result<Self> {
+        write_header(&mut writer, sample_rate, 0)?;
+
+        Ok(WavWriter {
+            writer,
+            sample_rate,
+            samples: 0
+        })
+    }
+
+    /// Gets the number of samples written so far.
+    pub fn get_sample_count(&self) -> u32 {
+        self.samples
+    }
+
+    /// Writes a sample from -1 to 1. Samples outside that range are clipped.
+    pub fn write_sample(&mut self, sample: f32) -> io::Result<()> {
+        let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+
+        self.samples += 1;
+        self.writer.write_all(&sample.to_le_bytes())
+    }
+
+    /// Fills in the header and flushes the file, returning the writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.seek(SeekFrom::Start(0))?;
+        write_header(&mut self.writer, self.sample_rate, self.samples)?;
+        self.writer.seek(SeekFrom::End(0))?;
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+}
+
+fn write_header<W: Write>(writer: &mut W, sample_rate: u32, samples: u32) -> io::Result<()> {
+    let block_align = BITS_PER_SAMPLE / 8;
+    let data_size = samples * block_align as u32;
+
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&(HEADER_SIZE - 8 + data_size).to_le_bytes())?;
+    writer.write_all(b"WAVE")?;
+    writer.write_all(b"fmt ")?;
+    writer.write_all(&16u32.to_le_bytes())?; // format chunk size
+    writer.write_all(&1u16.to_le_bytes())?; // PCM
+    writer.write_all(&1u16.to_le_bytes())?; // mono
+    writer.write_all(&sample_rate.to_le_bytes())?;
+    writer.write_all(&(sample_rate * block_align as u32).to_le_bytes())?; // bytes per second
+    writer.write_all(&block_align.to_le_bytes())?;
+    writer.write_all(&BITS_PER_SAMPLE.to_le_bytes())?;
+    writer.write_all(b"data")?;
+    writer.write_all(&data_size.to_le_bytes())
+}
+
+/// An audio sink that renders the beeper into a WAV file in emulated time, so the recording
+/// lines up sample for sample with the frames that were run however fast they ran.
+///
+/// Clones share the same recording. Keep one to call [`WavRecorder::finish`] with the CPU's
+/// elapsed time once it stops.
+pub struct WavRecorder<W: Write + Seek> {
+    inner: Rc<RefCell<Recording<W>>>
+}
+
+/// A recorder writing to a file.
+pub type WavFileRecorder = WavRecorder<BufWriter<File>>;
+
+struct Recording<W: Write + Seek> {
+    wav: Option<WavWriter<W>>,
+    beeper: Beeper,
+    error: Option<io::Error>
+}
+
+impl WavFileRecorder {
+    /// Creates a WAV file to record to.
+    pub fn create<P: AsRef<Path>>(path: P, settings: BeeperSettings) -> io::Result<Self> {
+        WavRecorder::new(BufWriter::new(File::create(path)?), settings)
+    }
+}
+
+impl<W: Write + Seek> WavRecorder<W> {
+    pub fn new(writer: W, settings: BeeperSettings) -> io::Result<Self> {
+        let recording = Recording {
+            wav: Some(WavWriter::new(writer, audio::SAMPLE_RATE)?),
+            beeper: Beeper::new(settings, audio::SAMPLE_RATE),
+            error: None
+        };
+
+        Ok(WavRecorder {
+            inner: Rc::new(RefCell::new(recording))
+        })
+    }
+
+    /// Renders up to the time the recording ends and completes the file, returning the first
+    /// error that occurred while writing it.
+    pub fn finish(&self, time: Duration) -> io::Result<W> {
+        let mut recording = self.inner.borrow_mut();
+
+        recording.render_until(time);
+
+        if let Some(err) = recording.error.take() {
+            return Err(err);
+        }
+
+        match recording.wav.take() {
+            Some(wav) => wav.finish(),
+            None => Err(io::Error::other("the recording was already finished"))
+        }
+    }
+
+    /// Renders up to a point in time, then switches to a new tone.
+    fn switch(&self, time: Duration, tone: Option<Tone>) {
+        let mut recording = self.inner.borrow_mut();
+
+        recording.render_until(time);
+        recording.beeper.play(tone);
+    }
+}
+
+impl<W: Write + Seek> Clone for WavRecorder<W> {
+    fn clone(&self) -> Self {
+        WavRecorder {
+            inner: Rc::clone(&self.inner)
+        }
+    }
+}
+
+impl<W: Write + Seek> Recording<W> {
+    /// Writes samples of the beeper up to a point in time. After a write fails, nothing more is written.
+    fn render_until(&mut self, time: Duration) {
+        let end = (time.as_nanos() * audio::SAMPLE_RATE as u128 / 1_000_000_000) as u32;
+        let wav = match (self.wav.as_mut(), self.error.is_none()) {
+            (Some(wav), true) => wav,
+            _ => return
+        };
+
+        while wav.get_sample_count() < end {
+            if let Err(err) = wav.write_sample(self.beeper.next_sample()) {
+                self.error = Some(err);
+                return;
+            }
+        }
+    }
+}
+
+impl<W: Write + Seek> AudioSink for WavRecorder<W> {
+    fn start(&mut self, time: Duration, tone: Tone) {
+        self.switch(time, Some(tone));
+    }
+
+    fn stop(&mut self, time: Duration) {
+        self.switch(time, None);
+    }
+}
@@ -0,0 +1,152 @@
+//! Checks when the CPU starts and stops sound, using a recording audio sink and a WAV recording.
+
+use ch8_core::audio::{self, Beeper, BeeperSettings, RecordingSink, Tone, Waveform};
+use ch8_core::cpu::Cpu;
+use ch8_core::wav::WavRecorder;
+
+use std::io::Cursor;
+use std::time::Duration;
+
+mod common;
+
+/// Creates a CPU with a program that plays into a recording sink.
+fn cpu_with(words: &[u16]) -> (Cpu, RecordingSink) {
+    let mut cpu = common::cpu_with(words);
+    let sink = RecordingSink::new();
+
+    cpu.set_audio_sink(Box::new(sink.clone()));
+
+    (cpu, sink)
+}
+
+#[test]
+fn sound_timer_starts_and_stops_sound() {
+    // set the sound timer to 3 frames, then loop forever
+    let (mut cpu, sink) = cpu_with(&[0x6003, 0xF018, 0x1204]);
+
+    for _ in 0..5 {
+        cpu.run_frame().unwrap();
+    }
+
+    // started by the second of 8 instructions in the first frame, stopped after 3 frames
+    assert_eq!(sink.get_intervals(), vec![(Duration::from_nanos(4_166_666), Some(Duration::from_millis(50)))]);
+    assert_eq!(sink.get_events()[0].tone, Some(Tone::Beep));
+}
+
+#[test]
+fn changing_pattern_while_playing_switches_tone() {
+    let (mut cpu, sink) = cpu_with(&[0x6010, 0xF018, 0xA300, 0xF002, 0x1208]);
+
+    cpu.get_memory_mut()[0x300] = 0xF0;
+    cpu.run_frame().unwrap();
+
+    let mut samples = [0; 16];
+
+    samples[0] = 0xF0;
+
+    let tones: Vec<Option<Tone>> = sink.get_events().iter().map(|event| event.tone).collect();
+
+    assert_eq!(tones, vec![Some(Tone::Beep), Some(Tone::Pattern {
+        samples,
+        pitch: 64
+    })]);
+    assert_eq!(sink.get_intervals().len(), 1);
+}
+
+#[test]
+fn new_sink_starts_playing_at_once() {
+    let (mut cpu, _) = cpu_with(&[0x6010, 0xF018]);
+
+    cpu.run_cycles(2).unwrap();
+
+    let sink = RecordingSink::new();
+
+    cpu.set_audio_sink(Box::new(sink.clone()));
+
+    assert_eq!(sink.get_intervals(), vec![(Duration::from_nanos(4_166_666), None)]);
+}
+
+#[test]
+fn wav_recording_is_timed_by_frames() {
+    let (mut cpu, _) = cpu_with(&[0x6003, 0xF018, 0x1204]);
+    let settings = BeeperSettings {
+        ramp: Duration::from_millis(0),
+        ..BeeperSettings::default()
+    };
+    let recorder = WavRecorder::new(Cursor::new(Vec::new()), settings).unwrap();
+
+    cpu.set_audio_sink(Box::new(recorder.clone()));
+
+    for _ in 0..6 {
+        cpu.run_frame().unwrap();
+    }
+
+    let wav = recorder.finish(cpu.get_elapsed_time()).unwrap().into_inner();
+    let samples: Vec<i16> = wav[44..]
+        .chunks_exact(2)
+        .map(|sample| i16::from_le_bytes([sample[0], sample[1]]))
+        .collect();
+
+    assert_eq!(&wav[0..4], b"RIFF");
+    assert_eq!(&wav[8..16], b"WAVEfmt ");
+    assert_eq!(u32::from_le_bytes([wav[40], wav[41], wav[42], wav[43]]) as usize, samples.len() * 2);
+
+    // a tenth of a second, with sound from 1/240 to 1/20 of a second in
+    let rate = audio::SAMPLE_RATE as usize;
+    let (start, end) = (rate / 240, rate / 20);
+
+    assert_eq!(samples.len(), rate / 10);
+    assert!(samples[..start].iter().all(|&sample| sample == 0));
+    assert!(samples[start..end].iter().any(|&sample| sample != 0));
+    assert!(samples[end..].iter().all(|&sample| sample == 0));
+}
+
+#[test]
+fn beeper_ramps_volume_up_and_down() {
+    let settings = BeeperSettings {
+        waveform: Waveform::Square,
+        frequency: 100.0,
+        volume: 0.5,
+        ramp: Duration::from_millis(1)
+    };
+    // 1 ms is 10 samples at 10 kHz
+    let mut beeper = Beeper::new(settings, 10_000);
+
+    assert_eq!(beeper.next_sample(), 0.0);
+
+    beeper.play(Some(Tone::Beep));
+
+    let attack: Vec<f32> = (0..12).map(|_| beeper.next_sample()).collect();
+
+    assert!((attack[0] - 0.05).abs() < 1e-6);
+    assert!(attack.windows(2).all(|pair| pair[1] >= pair[0]));
+    assert!((attack[11] - 0.5).abs() < 1e-6);
+
+    beeper.play(None);
+
+    let release: Vec<f32> = (0..12).map(|_| beeper.next_sample()).collect();
+
+    assert!(release[0] > 0.0 && release[0] < 0.5);
+    assert_eq!(release[11], 0.0);
+}
+
+#[test]
+fn beeper_waveforms() {
+    // a 2500 Hz wave at 10 kHz takes 4 samples per cycle
+    let sample = |waveform| {
+        let mut beeper = Beeper::new(BeeperSettings {
+            waveform,
+            frequency: 2500.0,
+            volume: 1.0,
+            ramp: Duration::from_millis(0)
+        }, 10_000);
+
+        beeper.play(Some(Tone::Beep));
+
+        (0..4).map(|_| (beeper.next_sample() * 100.0).round() / 100.0).collect::<Vec<f32>>()
+    };
+
+    assert_eq!(sample(Waveform::Square), vec![1.0, -1.0, -1.0, 1.0]);
+    assert_eq!(sample(Waveform::Sine), vec![1.0, 0.0, -1.0, 0.0]);
+    assert_eq!(sample(Waveform::Triangle), vec![0.0, 1.0, 0.0, -1.0]);
+}
@@ -0,0 +1,10 @@
+//! Helpers shared by the integration tests.
+
+use ch8_core::cpu::Cpu;
+
+/// Creates a CPU with a program given as instruction words, using [`Cpu::with_program`].
+pub fn cpu_with(words: &[u16]) -> Cpu {
+    let program: Vec<u8> = words.iter().flat_map(|word| word.to_be_bytes().to_vec()).collect();
+
+    Cpu::with_program(&program)
+}
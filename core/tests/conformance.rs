@@ -0,0 +1,22 @@
+//! Runs the built-in conformance checks, which have to pass under every preset they list.
+
+use ch8_core::conformance::{self, Outcome};
+
+#[test]
+fn conformance_checks() {
+    let results = conformance::run_checks();
+    let mut failures = String::new();
+
+    for result in results.iter() {
+        if result.outcome.is_failure() {
+            failures.push_str(&format!("{} under {}: {}\n", result.rom, result.preset, result.outcome));
+
+            if let Outcome::Fail(diff) = &result.outcome {
+                failures.push_str(&diff.to_string());
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "conformance checks failed:\n{}", failures);
+    assert_eq!(results.len(), conformance::CHECKS.iter().map(|rom| rom.references.len()).sum::<usize>());
+}
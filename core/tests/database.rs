@@ -0,0 +1,33 @@
+//! Checks hashing and identifying ROMs.
+
+use ch8_core::database::{self, Platform};
+use ch8_core::sha1;
+
+#[test]
+fn bundled_roms_are_identified() {
+    let pong = database::identify(include_bytes!("../../roms/pong.rom")).unwrap();
+
+    assert_eq!(pong.title, "Pong");
+    assert_eq!(pong.platform, Platform::ModernChip8);
+    assert_eq!(pong.platform.get_preset_name(), None);
+    assert_eq!(pong.to_string(), "Pong by Paul Vervalin (CHIP-8)");
+
+    assert_eq!(database::identify(include_bytes!("../../roms/invaders.rom")).unwrap().title, "Space Invaders");
+
+    let test_opcode = database::identify(include_bytes!("../../roms/test_opcode.rom")).unwrap();
+
+    assert_eq!(test_opcode.author, Some("corax89"));
+    assert_eq!(test_opcode.platform, Platform::Chip8);
+    assert_eq!(database::identify(&[0x12, 0x00]), None);
+}
+
+#[test]
+fn sha1_matches_known_digests() {
+    assert_eq!(sha1::hex_digest(b""), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+    assert_eq!(sha1::hex_digest(b"abc"), "a9993e364706816aba3e25717850c26c9cd0d89d");
+    assert_eq!(
+        sha1::hex_digest(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+        "84983e441c3bd26ebaae4aa1f95129e5e54670f1"
+    );
+    assert_eq!(sha1::hex_digest(include_bytes!("../../roms/pong.rom")), "b232ef880bd6060fb45fa6effed7edf0ae95670e");
+}
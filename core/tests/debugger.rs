@@ -0,0 +1,141 @@
+//! Checks that the debugger stops on breakpoints, watchpoints and conditions, and that resuming
+//! runs the instruction it stopped on.
+
+use ch8_core::cpu::Cpu;
+use ch8_core::debugger::{Access, Condition, Debugger, StopReason, WatchKind, Watchpoint, DEFAULT_STEP_LIMIT};
+
+/// Counts V0 up forever.
+const COUNTER: [u8; 6] = [
+    0x60, 0x00, // 0x200: V0 = 0
+    0x70, 0x01, // 0x202: V0 += 1
+    0x12, 0x02 // 0x204: jump to 0x202
+];
+
+/// Writes the digits of 5 to 0x300, then V0 to 0x310, then loops.
+const STORES: [u8; 12] = [
+    0xA3, 0x00, // 0x200: I = 0x300
+    0x60, 0x05, // 0x202: V0 = 5
+    0xF0, 0x33, // 0x204: BCD of V0 at I
+    0xA3, 0x10, // 0x206: I = 0x310
+    0xF0, 0x55, // 0x208: store V0 at I
+    0x12, 0x0A // 0x20A: loop forever
+];
+
+#[test]
+fn breakpoint_stops_before_the_instruction() {
+    let mut cpu = Cpu::with_program(&COUNTER);
+    let mut debugger = Debugger::new();
+
+    debugger.add_breakpoint(0x202);
+
+    assert_eq!(debugger.run(&mut cpu, 100), Some(StopReason::Breakpoint(0x202)));
+    assert_eq!(cpu.get_program_counter(), 0x202);
+    assert_eq!(cpu.get_registers()[0], 0);
+}
+
+#[test]
+fn resuming_runs_the_instruction_at_the_breakpoint() {
+    let mut cpu = Cpu::with_program(&COUNTER);
+    let mut debugger = Debugger::new();
+
+    debugger.add_breakpoint(0x202);
+    debugger.run(&mut cpu, 100);
+
+    // once around the loop, then stopped again
+    for count in 1..=3 {
+        assert_eq!(debugger.run(&mut cpu, 100), Some(StopReason::Breakpoint(0x202)));
+        assert_eq!(cpu.get_registers()[0], count);
+    }
+
+    debugger.remove_breakpoint(0x202);
+
+    assert_eq!(debugger.run(&mut cpu, 10), None);
+    assert_eq!(cpu.get_registers()[0], 8);
+}
+
+#[test]
+fn write_watchpoint_stops_before_the_write() {
+    let mut cpu = Cpu::with_program(&STORES);
+    let mut debugger = Debugger::new();
+
+    // reads of the BCD digits don't count, only writes to 0x310 do
+    debugger.add_watchpoint(Watchpoint {
+        range: 0x300..=0x302,
+        kind: WatchKind::Read
+    });
+    debugger.add_watchpoint(Watchpoint {
+        range: 0x310..=0x310,
+        kind: WatchKind::Write
+    });
+
+    assert_eq!(debugger.run(&mut cpu, 100), Some(StopReason::Watchpoint {
+        address: 0x310,
+        access: Access::Write
+    }));
+    assert_eq!(cpu.get_program_counter(), 0x208);
+    assert_eq!(&cpu.get_memory()[0x300..0x303], &[0, 0, 5]);
+    assert_eq!(cpu.get_memory()[0x310], 0);
+
+    // resuming makes the write and carries on
+    assert_eq!(debugger.run(&mut cpu, 10), None);
+    assert_eq!(cpu.get_memory()[0x310], 5);
+}
+
+#[test]
+fn watchpoint_reports_the_first_watched_address() {
+    let mut cpu = Cpu::with_program(&STORES);
+    let mut debugger = Debugger::new();
+
+    debugger.add_watchpoint(Watchpoint {
+        range: 0x301..=0x305,
+        kind: WatchKind::ReadWrite
+    });
+
+    assert_eq!(debugger.run(&mut cpu, 100), Some(StopReason::Watchpoint {
+        address: 0x301,
+        access: Access::Write
+    }));
+    assert_eq!(cpu.get_program_counter(), 0x204);
+}
+
+#[test]
+fn condition_stops_after_it_becomes_true() {
+    let mut cpu = Cpu::with_program(&COUNTER);
+    let mut debugger = Debugger::new();
+    let condition = Condition::parse("v0 == 3").unwrap();
+
+    debugger.add_condition(condition);
+
+    assert_eq!(debugger.run(&mut cpu, 100), Some(StopReason::Condition(condition)));
+    assert_eq!(cpu.get_registers()[0], 3);
+    assert_eq!(cpu.get_program_counter(), 0x204);
+
+    // it only stops again when it becomes true again, which a counter never does
+    assert_eq!(debugger.run(&mut cpu, 100), None);
+}
+
+#[test]
+fn step_over_runs_the_whole_subroutine() {
+    let mut cpu = Cpu::with_program(&[
+        0x22, 0x06, // 0x200: call 0x206
+        0x61, 0x01, // 0x202: V1 = 1
+        0x12, 0x04, // 0x204: loop forever
+        0x60, 0x07, // 0x206: V0 = 7
+        0x00, 0xEE // 0x208: return
+    ]);
+    let mut debugger = Debugger::new();
+
+    assert_eq!(debugger.step_over(&mut cpu, DEFAULT_STEP_LIMIT), StopReason::Step);
+    assert_eq!(cpu.get_program_counter(), 0x202);
+    assert_eq!(cpu.get_registers()[0], 7);
+    assert_eq!(cpu.get_registers()[1], 0);
+
+    // a breakpoint inside the subroutine still stops it
+    let mut cpu = Cpu::with_program(&cpu.get_memory()[0x200..0x20A]);
+
+    debugger.add_breakpoint(0x208);
+
+    assert_eq!(debugger.step_over(&mut cpu, DEFAULT_STEP_LIMIT), StopReason::Breakpoint(0x208));
+    assert_eq!(debugger.step_out(&mut cpu, DEFAULT_STEP_LIMIT), StopReason::Step);
+    assert_eq!(cpu.get_program_counter(), 0x202);
+}
@@ -0,0 +1,108 @@
+//! Golden-screenshot regression tests. Each case runs a ROM headless for a fixed number of frames
+//! with scripted input, then compares the display with a reference image, in `tests/golden` or
+//! for ROMs shared with the conformance checks in `conformance`.
+//!
+//! Run with `CH8_BLESS=1` to write the reference images from the current output after checking
+//! that it's correct.
+
+use ch8_core::cpu::Cpu;
+use ch8_core::headless::{self, InputScript, RunOptions};
+use ch8_core::quirks::Quirks;
+use ch8_core::screenshot::Screenshot;
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+struct Case {
+    name: &'static str,
+    /// ROM, relative to the `ch8-core` directory.
+    rom: &'static str,
+    /// Reference image, relative to the `ch8-core` directory.
+    reference: &'static str,
+    frames: u32,
+    input: &'static str,
+    quirks: Quirks
+}
+
+const SEED: u64 = 0;
+
+fn run_case(case: &Case) {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let rom = fs::read(root.join(case.rom)).expect("Couldn't read the ROM.");
+    let reference_path = root.join(case.reference);
+
+    let mut cpu = Cpu::new();
+
+    cpu.init();
+    cpu.set_quirks(case.quirks);
+    cpu.seed_rng(SEED);
+    cpu.load_program(&rom).expect("The ROM doesn't fit in memory.");
+
+    let options = RunOptions {
+        frames: case.frames,
+        input: InputScript::parse(case.input).expect("Bad input script."),
+        ..RunOptions::default()
+    };
+    let result = headless::run(&mut cpu, &options);
+
+    assert_eq!(result.stop, None, "{} stopped early", case.name);
+
+    let screenshot = Screenshot::from_display(cpu.get_display());
+
+    if env::var_os("CH8_BLESS").is_some() {
+        fs::write(&reference_path, screenshot.to_pbm()).expect("Couldn't write the reference image.");
+        return;
+    }
+
+    let reference = fs::read_to_string(&reference_path).unwrap_or_else(|err| {
+        panic!("Couldn't read {}: {}. Run with CH8_BLESS=1 to create it.", reference_path.display(), err)
+    });
+    let reference = Screenshot::from_pbm(&reference).expect("Bad reference image.");
+
+    if let Some(diff) = screenshot.diff(&reference) {
+        panic!("{} doesn't match {}\n{}", case.name, reference_path.display(), diff);
+    }
+}
+
+#[test]
+fn test_opcode() {
+    run_case(&Case {
+        name: "test_opcode",
+        rom: "conformance/test_opcode.rom",
+        reference: "conformance/test_opcode.pbm",
+        frames: 60,
+        input: "",
+        quirks: Quirks::default()
+    });
+}
+
+#[test]
+fn pong_with_paddle_moved() {
+    run_case(&Case {
+        name: "pong",
+        rom: "../roms/pong.rom",
+        reference: "tests/golden/pong.pbm",
+        frames: 120,
+        input: "
+            # move the left paddle up, then down past where it started
+            10 down 1
+            30 up 1
+            40 down 4
+            80 up 4
+        ",
+        quirks: Quirks::cosmac_vip()
+    });
+}
+
+#[test]
+fn invaders_title() {
+    run_case(&Case {
+        name: "invaders",
+        rom: "../roms/invaders.rom",
+        reference: "tests/golden/invaders.pbm",
+        frames: 180,
+        input: "",
+        quirks: Quirks::default()
+    });
+}
@@ -0,0 +1,78 @@
+//! Checks parsing input scripts for the headless runner.
+
+use ch8_core::cpu::Cpu;
+use ch8_core::headless::{InputScript, KeyEvent, TAP_FRAMES};
+
+fn event(frame: u32, key: u8, pressed: bool) -> KeyEvent {
+    KeyEvent {
+        frame,
+        key,
+        pressed
+    }
+}
+
+fn error(text: &str) -> String {
+    InputScript::parse(text).unwrap_err().to_string()
+}
+
+#[test]
+fn actions_become_events_in_frame_order() {
+    let script = InputScript::parse(
+        "
+        # frame action key
+        90 up 5
+        60 down 5   # pressed first
+        120 tap a
+        "
+    )
+    .unwrap();
+
+    assert_eq!(script.get_events(), &[
+        event(60, 0x5, true),
+        event(90, 0x5, false),
+        event(120, 0xA, true),
+        event(120 + TAP_FRAMES, 0xA, false)
+    ]);
+}
+
+#[test]
+fn events_are_applied_on_their_frame() {
+    let script = InputScript::parse("2 tap F").unwrap();
+    let mut cpu = Cpu::new();
+
+    for frame in 0..=2 {
+        script.apply(frame, &mut cpu);
+    }
+
+    assert!(cpu.get_keyboard().is_key_pressed(0xF));
+
+    script.apply(2 + TAP_FRAMES, &mut cpu);
+
+    assert!(!cpu.get_keyboard().is_key_pressed(0xF));
+}
+
+#[test]
+fn empty_script_has_no_events() {
+    assert!(InputScript::parse("\n   \n# nothing yet\n").unwrap().get_events().is_empty());
+}
+
+#[test]
+fn errors_give_the_line() {
+    assert_eq!(error("1 down 5\n2 down 10"), "line 2: key must be a hex digit 0-F");
+    assert_eq!(error("1 down G"), "line 1: key must be a hex digit 0-F");
+    assert_eq!(error("-1 down 5"), "line 1: frame must be a number");
+    assert_eq!(error("4294967296 down 5"), "line 1: frame must be a number");
+    assert_eq!(error("soon down 5"), "line 1: frame must be a number");
+    assert_eq!(error("1 press 5"), "line 1: action must be down, up or tap");
+    assert_eq!(error("1 down"), "line 1: expected a frame, an action and a key");
+    assert_eq!(error("1 down 5 6"), "line 1: expected a frame, an action and a key");
+}
+
+#[test]
+fn tap_that_cant_be_released_is_refused() {
+    let last = u32::MAX - TAP_FRAMES;
+
+    assert_eq!(InputScript::parse(&format!("{} tap 1", last)).unwrap().get_events()[1].frame, u32::MAX);
+    assert_eq!(error(&format!("{} tap 1", last + 1)), "line 1: tap is too late to be released");
+    assert_eq!(error(&format!("{} tap 1", u32::MAX)), "line 1: tap is too late to be released");
+}
@@ -0,0 +1,90 @@
+//! Checks loading ROM images into memory, and the errors and warnings for ones that don't fit.
+
+use ch8_core::cpu::{self, Cpu, LoadError, LoadWarning};
+use ch8_core::quirks::Quirks;
+
+#[test]
+fn largest_rom_fills_memory() {
+    let mut cpu = Cpu::with_program(&[]);
+    let rom = vec![0xAB; cpu::MEMORY_SIZE - cpu::PROGRAM_START];
+
+    assert_eq!(cpu.load_program(&rom), Ok(()));
+    assert_eq!(cpu.get_memory()[cpu::MEMORY_SIZE - 1], 0xAB);
+    assert_eq!(cpu.get_program_counter(), cpu::PROGRAM_START);
+}
+
+#[test]
+fn oversized_rom_is_reported() {
+    let mut cpu = Cpu::with_program(&[]);
+    let rom = vec![0xAB; 3585];
+    let err = cpu.load_program(&rom).unwrap_err();
+
+    assert_eq!(err, LoadError::TooLarge {
+        size: 3585,
+        address: cpu::PROGRAM_START,
+        memory_size: cpu::MEMORY_SIZE
+    });
+    assert_eq!(err.to_string(), "ROM is 3585 bytes, but only 3584 bytes fit from 0x200 in 4K of memory");
+    // nothing is copied
+    assert!(cpu.get_memory()[cpu::PROGRAM_START..].iter().all(|&byte| byte == 0));
+
+    // XO-CHIP programs have the whole 64K address space
+    cpu.set_quirks(Quirks::modern());
+
+    assert_eq!(cpu.get_memory_size(), cpu::XO_CHIP_MEMORY_SIZE);
+
+    assert_eq!(cpu.load_program(&rom), Ok(()));
+}
+
+#[test]
+fn memory_size_is_kept_in_range() {
+    let mut cpu = Cpu::with_program(&[]);
+
+    cpu.set_memory_size(0);
+    assert_eq!(cpu.get_memory_size(), cpu::MEMORY_SIZE);
+
+    cpu.set_memory_size(0x100);
+    cpu.init();
+    assert_eq!(cpu.get_memory_size(), cpu::MEMORY_SIZE);
+
+    cpu.set_memory_size(0x100000);
+    assert_eq!(cpu.get_memory_size(), cpu::XO_CHIP_MEMORY_SIZE);
+}
+
+#[test]
+fn eti_660_programs_load_and_start_at_0x600() {
+    let mut cpu = Cpu::with_program(&[]);
+
+    cpu.load_program_at(&[0x60, 0x2A], cpu::ETI_660_PROGRAM_START).unwrap();
+
+    assert_eq!(cpu.get_program_counter(), 0x600);
+    assert_eq!(&cpu.get_memory()[0x600..0x602], &[0x60, 0x2A]);
+
+    cpu.run_cycles(1).unwrap();
+
+    assert_eq!(cpu.get_registers()[0], 0x2A);
+
+    let too_large = vec![0; cpu::MEMORY_SIZE - 0x600 + 1];
+
+    assert!(matches!(cpu.load_program_at(&too_large, 0x600), Err(LoadError::TooLarge { .. })));
+}
+
+#[test]
+fn loading_over_the_font_is_refused() {
+    let mut cpu = Cpu::with_program(&[]);
+
+    assert_eq!(cpu.load_program_at(&[0x00, 0xE0], 0x10), Err(LoadError::OverlapsFont {
+        address: 0x10
+    }));
+    assert_eq!(&cpu.get_memory()[..5], &cpu::FONT_SPRITES[..5]);
+}
+
+#[test]
+fn odd_and_empty_images_are_warned_about() {
+    assert_eq!(cpu::check_program(&[]), vec![LoadWarning::Empty]);
+    assert_eq!(cpu::check_program(&[0x00, 0xE0, 0x12]), vec![LoadWarning::OddLength(3)]);
+    assert!(cpu::check_program(include_bytes!("../../roms/pong.rom")).is_empty());
+
+    // they still load
+    assert_eq!(Cpu::with_program(&[]).load_program(&[]), Ok(()));
+}
@@ -0,0 +1,844 @@
+//! One test per opcode, built on small programs assembled from instruction words. Edge cases
+//! cover carry and borrow, VF used as an operand, where the flag has to be written after the
+//! result, and sprites wrapping or clipping at the edges of the screen.
+
+use ch8_core::cpu::{Cpu, BIG_FONT_SPRITES, FONT_SPRITES};
+use ch8_core::fault::{FaultAction, FaultKind, FaultPolicy};
+use ch8_core::quirks::Quirks;
+
+use common::cpu_with;
+
+mod common;
+
+const START: usize = 0x200;
+
+fn run(cpu: &mut Cpu, instructions: u32) {
+    cpu.run_cycles(instructions).expect("unexpected fault");
+}
+
+fn fault_kind(cpu: &mut Cpu) -> FaultKind {
+    cpu.step().expect_err("expected a fault").kind
+}
+
+fn v(cpu: &Cpu, register: usize) -> u8 {
+    cpu.get_registers()[register]
+}
+
+#[test]
+fn machine_call_faults_by_default() {
+    let mut cpu = cpu_with(&[0x0123]);
+
+    assert_eq!(fault_kind(&mut cpu), FaultKind::MachineCodeCall);
+    assert_eq!(cpu.get_program_counter(), START);
+}
+
+#[test]
+fn machine_call_is_skipped_when_ignored() {
+    let mut cpu = cpu_with(&[0x0123]);
+
+    cpu.set_fault_policy(FaultPolicy::all(FaultAction::Ignore));
+    run(&mut cpu, 1);
+
+    assert_eq!(cpu.get_program_counter(), START + 2);
+}
+
+#[test]
+fn every_recovered_fault_is_reported() {
+    // three machine code calls in a row, all skipped in one frame
+    let mut cpu = cpu_with(&[0x0123, 0x0456, 0x0789, 0x1206]);
+
+    cpu.set_fault_policy(FaultPolicy::all(FaultAction::Ignore));
+    cpu.run_frame().unwrap();
+
+    let addresses: Vec<usize> = std::iter::from_fn(|| cpu.take_recovered_fault())
+        .map(|fault| fault.address)
+        .collect();
+
+    assert_eq!(addresses, vec![START, START + 2, START + 4]);
+    assert_eq!(cpu.take_dropped_fault_count(), 0);
+}
+
+#[test]
+fn recovered_faults_past_the_limit_are_counted() {
+    // a machine code call in a loop, recovered 4 times a frame, so 80 faults in 20 frames
+    let mut cpu = cpu_with(&[0x0123, 0x1200]);
+
+    cpu.set_fault_policy(FaultPolicy::all(FaultAction::Ignore));
+
+    for _ in 0..20 {
+        cpu.run_frame().unwrap();
+    }
+
+    assert_eq!(std::iter::from_fn(|| cpu.take_recovered_fault()).count(), 64);
+    assert_eq!(cpu.take_dropped_fault_count(), 16);
+    assert_eq!(cpu.take_dropped_fault_count(), 0);
+}
+
+#[test]
+fn clear_display() {
+    let mut cpu = cpu_with(&[0xA000, 0xD005, 0x00E0]);
+
+    run(&mut cpu, 2);
+    assert!(cpu.get_display().is_set_at(0, 0));
+
+    run(&mut cpu, 1);
+    assert!(!cpu.get_display().is_set_at(0, 0));
+}
+
+#[test]
+fn scroll_down() {
+    let mut cpu = cpu_with(&[0xA000, 0xD001, 0x00C2]);
+
+    run(&mut cpu, 3);
+
+    assert!(!cpu.get_display().is_set_at(0, 0));
+    assert!(cpu.get_display().is_set_at(0, 2));
+}
+
+#[test]
+fn scroll_up() {
+    let mut cpu = cpu_with(&[0x6105, 0xA000, 0xD011, 0x00D3]);
+
+    run(&mut cpu, 4);
+
+    assert!(!cpu.get_display().is_set_at(0, 5));
+    assert!(cpu.get_display().is_set_at(0, 2));
+}
+
+#[test]
+fn scroll_right() {
+    let mut cpu = cpu_with(&[0xA000, 0xD001, 0x00FB]);
+
+    run(&mut cpu, 3);
+
+    assert!(!cpu.get_display().is_set_at(0, 0));
+    assert!(cpu.get_display().is_set_at(4, 0));
+    assert!(cpu.get_display().is_set_at(7, 0));
+}
+
+#[test]
+fn scroll_left() {
+    let mut cpu = cpu_with(&[0x6008, 0xA000, 0xD011, 0x00FC]);
+
+    run(&mut cpu, 4);
+
+    assert!(cpu.get_display().is_set_at(4, 0));
+    assert!(cpu.get_display().is_set_at(7, 0));
+    assert!(!cpu.get_display().is_set_at(8, 0));
+}
+
+#[test]
+fn exit_stops_execution() {
+    let mut cpu = cpu_with(&[0x00FD, 0x6001]);
+
+    run(&mut cpu, 3);
+
+    assert!(cpu.has_exited());
+    assert_eq!(cpu.get_program_counter(), START + 2);
+    assert_eq!(v(&cpu, 0), 0);
+}
+
+#[test]
+fn high_and_low_resolution() {
+    let mut cpu = cpu_with(&[0x00FF, 0x00FE]);
+
+    run(&mut cpu, 1);
+    assert!(cpu.get_display().is_hires());
+    assert_eq!(cpu.get_display().get_width(), 128);
+
+    run(&mut cpu, 1);
+    assert!(!cpu.get_display().is_hires());
+    assert_eq!(cpu.get_display().get_width(), 64);
+}
+
+#[test]
+fn call_and_return() {
+    let mut cpu = cpu_with(&[0x2206, 0x6101, 0x1204, 0x00EE]);
+
+    run(&mut cpu, 1);
+    assert_eq!(cpu.get_program_counter(), 0x206);
+    assert_eq!(cpu.get_stack(), &[0x202]);
+
+    run(&mut cpu, 1);
+    assert_eq!(cpu.get_program_counter(), 0x202);
+    assert!(cpu.get_stack().is_empty());
+}
+
+#[test]
+fn return_with_empty_stack_faults() {
+    let mut cpu = cpu_with(&[0x00EE]);
+
+    assert_eq!(fault_kind(&mut cpu), FaultKind::StackUnderflow);
+}
+
+#[test]
+fn call_with_full_stack_faults() {
+    let mut cpu = cpu_with(&[0x2200]);
+
+    run(&mut cpu, 16);
+
+    assert_eq!(fault_kind(&mut cpu), FaultKind::StackOverflow);
+}
+
+#[test]
+fn jump() {
+    let mut cpu = cpu_with(&[0x1234]);
+
+    run(&mut cpu, 1);
+
+    assert_eq!(cpu.get_program_counter(), 0x234);
+}
+
+#[test]
+fn skip_if_equal_to_value() {
+    let mut cpu = cpu_with(&[0x6005, 0x3005, 0x3006]);
+
+    run(&mut cpu, 2);
+    assert_eq!(cpu.get_program_counter(), START + 6);
+
+    cpu.set_program_counter(START + 4);
+    run(&mut cpu, 1);
+    assert_eq!(cpu.get_program_counter(), START + 6);
+}
+
+#[test]
+fn skip_if_not_equal_to_value() {
+    let mut cpu = cpu_with(&[0x6005, 0x4006, 0x4005]);
+
+    run(&mut cpu, 2);
+    assert_eq!(cpu.get_program_counter(), START + 6);
+
+    cpu.set_program_counter(START + 4);
+    run(&mut cpu, 1);
+    assert_eq!(cpu.get_program_counter(), START + 6);
+}
+
+#[test]
+fn skip_if_registers_equal() {
+    let mut cpu = cpu_with(&[0x6005, 0x6105, 0x5010, 0x0000, 0x6206, 0x5020]);
+
+    run(&mut cpu, 3);
+    assert_eq!(cpu.get_program_counter(), START + 8);
+
+    run(&mut cpu, 2);
+    assert_eq!(cpu.get_program_counter(), START + 12);
+}
+
+#[test]
+fn skip_if_registers_not_equal() {
+    let mut cpu = cpu_with(&[0x6005, 0x6106, 0x9010, 0x0000, 0x6205, 0x9020]);
+
+    run(&mut cpu, 3);
+    assert_eq!(cpu.get_program_counter(), START + 8);
+
+    run(&mut cpu, 2);
+    assert_eq!(cpu.get_program_counter(), START + 12);
+}
+
+#[test]
+fn skip_over_long_index_load() {
+    let mut cpu = cpu_with(&[0x3000, 0xF000, 0x1234, 0x6105]);
+
+    run(&mut cpu, 2);
+
+    assert_eq!(v(&cpu, 1), 5);
+    assert_eq!(cpu.get_index(), 0);
+}
+
+#[test]
+fn save_range() {
+    let mut cpu = cpu_with(&[0x6001, 0x6102, 0x6203, 0xA300, 0x5022, 0xA310, 0x5202]);
+
+    run(&mut cpu, 7);
+
+    assert_eq!(&cpu.get_memory()[0x300..0x303], &[1, 2, 3]);
+    assert_eq!(&cpu.get_memory()[0x310..0x313], &[3, 2, 1]);
+    assert_eq!(cpu.get_index(), 0x310);
+}
+
+#[test]
+fn load_range() {
+    let mut cpu = cpu_with(&[0xA300, 0x5023, 0x5533]);
+
+    cpu.get_memory_mut()[0x300..0x303].copy_from_slice(&[1, 2, 3]);
+    run(&mut cpu, 3);
+
+    assert_eq!(&cpu.get_registers()[0..3], &[1, 2, 3]);
+    assert_eq!(&cpu.get_registers()[3..6], &[3, 2, 1]);
+    assert_eq!(cpu.get_index(), 0x300);
+}
+
+#[test]
+fn set_value() {
+    let mut cpu = cpu_with(&[0x6A42]);
+
+    run(&mut cpu, 1);
+
+    assert_eq!(v(&cpu, 0xA), 0x42);
+}
+
+#[test]
+fn add_value_wraps_without_flag() {
+    let mut cpu = cpu_with(&[0x60FF, 0x6F07, 0x7002]);
+
+    run(&mut cpu, 3);
+
+    assert_eq!(v(&cpu, 0), 1);
+    assert_eq!(v(&cpu, 0xF), 7);
+}
+
+#[test]
+fn copy() {
+    let mut cpu = cpu_with(&[0x6107, 0x8010]);
+
+    run(&mut cpu, 2);
+
+    assert_eq!(v(&cpu, 0), 7);
+}
+
+#[test]
+fn logic() {
+    let mut cpu = cpu_with(&[0x600C, 0x610A, 0x8011, 0x620C, 0x8212, 0x630C, 0x8313]);
+
+    run(&mut cpu, 7);
+
+    assert_eq!(v(&cpu, 0), 0x0E);
+    assert_eq!(v(&cpu, 2), 0x08);
+    assert_eq!(v(&cpu, 3), 0x06);
+}
+
+#[test]
+fn logic_resets_flag_with_quirk() {
+    for (quirks, expected) in [(Quirks::super_chip(), 9), (Quirks::cosmac_vip(), 0)].iter() {
+        for op in 1..=3 {
+            let mut cpu = cpu_with(&[0x6F09, 0x8010 | op]);
+
+            cpu.set_quirks(*quirks);
+            run(&mut cpu, 2);
+
+            assert_eq!(v(&cpu, 0xF), *expected, "8XY{}", op);
+        }
+    }
+}
+
+#[test]
+fn add_with_carry() {
+    let mut cpu = cpu_with(&[0x60FF, 0x6102, 0x8014]);
+
+    run(&mut cpu, 3);
+
+    assert_eq!(v(&cpu, 0), 1);
+    assert_eq!(v(&cpu, 0xF), 1);
+}
+
+#[test]
+fn add_without_carry() {
+    let mut cpu = cpu_with(&[0x6F01, 0x60FD, 0x6102, 0x8014]);
+
+    run(&mut cpu, 4);
+
+    assert_eq!(v(&cpu, 0), 0xFF);
+    assert_eq!(v(&cpu, 0xF), 0);
+}
+
+#[test]
+fn add_into_flag_register_keeps_flag() {
+    // 1 + 1 leaves 2 in VF, which the carry flag then overwrites
+    let mut cpu = cpu_with(&[0x6F01, 0x6001, 0x8F04]);
+
+    run(&mut cpu, 3);
+
+    assert_eq!(v(&cpu, 0xF), 0);
+}
+
+#[test]
+fn add_from_flag_register_uses_old_value() {
+    let mut cpu = cpu_with(&[0x6F80, 0x6080, 0x80F4]);
+
+    run(&mut cpu, 3);
+
+    assert_eq!(v(&cpu, 0), 0);
+    assert_eq!(v(&cpu, 0xF), 1);
+}
+
+#[test]
+fn subtract_without_borrow() {
+    let mut cpu = cpu_with(&[0x6005, 0x6103, 0x8015]);
+
+    run(&mut cpu, 3);
+
+    assert_eq!(v(&cpu, 0), 2);
+    assert_eq!(v(&cpu, 0xF), 1);
+}
+
+#[test]
+fn subtract_with_borrow() {
+    let mut cpu = cpu_with(&[0x6F01, 0x6003, 0x6105, 0x8015]);
+
+    run(&mut cpu, 4);
+
+    assert_eq!(v(&cpu, 0), 0xFE);
+    assert_eq!(v(&cpu, 0xF), 0);
+}
+
+#[test]
+fn subtract_equal_values_does_not_borrow() {
+    let mut cpu = cpu_with(&[0x6005, 0x6105, 0x8015]);
+
+    run(&mut cpu, 3);
+
+    assert_eq!(v(&cpu, 0), 0);
+    assert_eq!(v(&cpu, 0xF), 1);
+}
+
+#[test]
+fn subtract_into_flag_register_keeps_flag() {
+    let mut cpu = cpu_with(&[0x6F03, 0x6005, 0x8F05]);
+
+    run(&mut cpu, 3);
+
+    assert_eq!(v(&cpu, 0xF), 0);
+}
+
+#[test]
+fn difference_without_borrow() {
+    let mut cpu = cpu_with(&[0x6003, 0x6105, 0x8017]);
+
+    run(&mut cpu, 3);
+
+    assert_eq!(v(&cpu, 0), 2);
+    assert_eq!(v(&cpu, 0xF), 1);
+}
+
+#[test]
+fn difference_with_borrow() {
+    let mut cpu = cpu_with(&[0x6F01, 0x6005, 0x6103, 0x8017]);
+
+    run(&mut cpu, 4);
+
+    assert_eq!(v(&cpu, 0), 0xFE);
+    assert_eq!(v(&cpu, 0xF), 0);
+}
+
+#[test]
+fn difference_into_flag_register_keeps_flag() {
+    let mut cpu = cpu_with(&[0x6F05, 0x6003, 0x8F07]);
+
+    run(&mut cpu, 3);
+
+    assert_eq!(v(&cpu, 0xF), 0);
+}
+
+#[test]
+fn shift_right_in_place() {
+    let mut cpu = cpu_with(&[0x6005, 0x6110, 0x8016]);
+
+    run(&mut cpu, 3);
+
+    assert_eq!(v(&cpu, 0), 2);
+    assert_eq!(v(&cpu, 0xF), 1);
+}
+
+#[test]
+fn shift_right_from_vy_with_quirk() {
+    let mut cpu = cpu_with(&[0x6005, 0x6110, 0x8016]);
+
+    cpu.set_quirks(Quirks::cosmac_vip());
+    run(&mut cpu, 3);
+
+    assert_eq!(v(&cpu, 0), 8);
+    assert_eq!(v(&cpu, 0xF), 0);
+}
+
+#[test]
+fn shift_right_flag_register_keeps_flag() {
+    let mut cpu = cpu_with(&[0x6F02, 0x8F06]);
+
+    run(&mut cpu, 2);
+
+    assert_eq!(v(&cpu, 0xF), 0);
+}
+
+#[test]
+fn shift_left_in_place() {
+    let mut cpu = cpu_with(&[0x6081, 0x6101, 0x801E]);
+
+    run(&mut cpu, 3);
+
+    assert_eq!(v(&cpu, 0), 2);
+    assert_eq!(v(&cpu, 0xF), 1);
+}
+
+#[test]
+fn shift_left_from_vy_with_quirk() {
+    let mut cpu = cpu_with(&[0x6081, 0x6101, 0x801E]);
+
+    cpu.set_quirks(Quirks::cosmac_vip());
+    run(&mut cpu, 3);
+
+    assert_eq!(v(&cpu, 0), 2);
+    assert_eq!(v(&cpu, 0xF), 0);
+}
+
+#[test]
+fn shift_left_flag_register_keeps_flag() {
+    let mut cpu = cpu_with(&[0x6F40, 0x8FFE]);
+
+    run(&mut cpu, 2);
+
+    assert_eq!(v(&cpu, 0xF), 0);
+}
+
+#[test]
+fn set_index() {
+    let mut cpu = cpu_with(&[0xA123]);
+
+    run(&mut cpu, 1);
+
+    assert_eq!(cpu.get_index(), 0x123);
+}
+
+#[test]
+fn jump_with_offset_from_v0() {
+    let mut cpu = cpu_with(&[0x6004, 0x6302, 0xB300]);
+
+    cpu.set_quirks(Quirks::cosmac_vip());
+    run(&mut cpu, 3);
+
+    assert_eq!(cpu.get_program_counter(), 0x304);
+}
+
+#[test]
+fn jump_with_offset_from_vx_with_quirk() {
+    let mut cpu = cpu_with(&[0x6004, 0x6302, 0xB300]);
+
+    cpu.set_quirks(Quirks::super_chip());
+    run(&mut cpu, 3);
+
+    assert_eq!(cpu.get_program_counter(), 0x302);
+}
+
+#[test]
+fn random_is_masked_and_seeded() {
+    let program = [0xC00F, 0xC1F0, 0xC200];
+    let mut first = cpu_with(&program);
+    let mut second = cpu_with(&program);
+
+    run(&mut first, 3);
+    run(&mut second, 3);
+
+    assert_eq!(v(&first, 0) & 0xF0, 0);
+    assert_eq!(v(&first, 1) & 0x0F, 0);
+    assert_eq!(v(&first, 2), 0);
+    assert_eq!(first.get_registers(), second.get_registers());
+}
+
+#[test]
+fn draw_sprite_sets_collision_flag() {
+    let mut cpu = cpu_with(&[0xA000, 0xD005, 0xD005]);
+
+    run(&mut cpu, 2);
+    assert!(cpu.get_display().is_set_at(3, 0));
+    assert!(!cpu.get_display().is_set_at(4, 0));
+    assert_eq!(v(&cpu, 0xF), 0);
+
+    run(&mut cpu, 1);
+    assert!(!cpu.get_display().is_set_at(3, 0));
+    assert_eq!(v(&cpu, 0xF), 1);
+}
+
+#[test]
+fn draw_sprite_wraps_start_position() {
+    let mut cpu = cpu_with(&[0x6044, 0x6121, 0xA000, 0xD011]);
+
+    run(&mut cpu, 4);
+
+    assert!(cpu.get_display().is_set_at(4, 1));
+}
+
+#[test]
+fn draw_sprite_clips_at_edges_with_quirk() {
+    let mut cpu = cpu_with(&[0x603E, 0x611F, 0xA000, 0xD015]);
+
+    cpu.set_quirks(Quirks::super_chip());
+    run(&mut cpu, 4);
+
+    assert!(cpu.get_display().is_set_at(62, 31));
+    assert!(cpu.get_display().is_set_at(63, 31));
+    assert!(!cpu.get_display().is_set_at(0, 31));
+    assert!(!cpu.get_display().is_set_at(62, 0));
+}
+
+#[test]
+fn draw_sprite_wraps_at_edges_without_quirk() {
+    let mut cpu = cpu_with(&[0x603E, 0x611F, 0xA000, 0xD015]);
+
+    cpu.set_quirks(Quirks::modern());
+    run(&mut cpu, 4);
+
+    // the "0" glyph's top row wraps horizontally and its sides wrap onto the top rows
+    assert!(cpu.get_display().is_set_at(62, 31));
+    assert!(cpu.get_display().is_set_at(1, 31));
+    assert!(cpu.get_display().is_set_at(62, 0));
+    assert!(!cpu.get_display().is_set_at(63, 0));
+    assert!(cpu.get_display().is_set_at(1, 0));
+    assert!(cpu.get_display().is_set_at(0, 3));
+}
+
+#[test]
+fn draw_large_sprite_in_high_resolution() {
+    let mut cpu = cpu_with(&[0x00FF, 0xA300, 0xD010]);
+
+    cpu.get_memory_mut()[0x300..0x320].iter_mut().for_each(|byte| *byte = 0xFF);
+    run(&mut cpu, 3);
+
+    assert!(cpu.get_display().is_set_at(15, 15));
+    assert!(!cpu.get_display().is_set_at(16, 0));
+    assert!(!cpu.get_display().is_set_at(0, 16));
+}
+
+#[test]
+fn draw_sprite_waits_for_display_with_quirk() {
+    let mut cpu = cpu_with(&[0xD001, 0x6005]);
+
+    cpu.set_quirks(Quirks::cosmac_vip());
+    run(&mut cpu, 2);
+    assert_eq!(v(&cpu, 0), 0);
+
+    cpu.decrement_timers();
+    run(&mut cpu, 1);
+    assert_eq!(v(&cpu, 0), 5);
+}
+
+#[test]
+fn skip_if_key_pressed() {
+    let mut cpu = cpu_with(&[0x6005, 0xE09E, 0xE09E]);
+
+    run(&mut cpu, 2);
+    assert_eq!(cpu.get_program_counter(), START + 4);
+
+    cpu.get_keyboard().set_key_pressed(5, true);
+    run(&mut cpu, 1);
+    assert_eq!(cpu.get_program_counter(), START + 8);
+}
+
+#[test]
+fn skip_if_key_not_pressed() {
+    let mut cpu = cpu_with(&[0x6005, 0xE0A1, 0xE0A1]);
+
+    run(&mut cpu, 2);
+    assert_eq!(cpu.get_program_counter(), START + 6);
+
+    cpu.set_program_counter(START + 4);
+    cpu.get_keyboard().set_key_pressed(5, true);
+    run(&mut cpu, 1);
+    assert_eq!(cpu.get_program_counter(), START + 6);
+}
+
+#[test]
+fn key_above_f_faults_by_default() {
+    let mut cpu = cpu_with(&[0x6020, 0xE09E]);
+
+    run(&mut cpu, 1);
+
+    assert_eq!(fault_kind(&mut cpu), FaultKind::InvalidKey);
+    assert_eq!(cpu.get_program_counter(), START + 2);
+}
+
+#[test]
+fn key_above_f_is_masked_when_recovered() {
+    for action in [FaultAction::Wrap, FaultAction::Ignore] {
+        // 0x25 checks key 5
+        let mut cpu = cpu_with(&[0x6025, 0xE09E, 0xE0A1]);
+
+        cpu.set_fault_policy(FaultPolicy::all(action));
+        cpu.get_keyboard().set_key_pressed(5, true);
+        run(&mut cpu, 2);
+
+        assert_eq!(cpu.get_program_counter(), START + 6, "{:?}", action);
+        assert_eq!(cpu.take_recovered_fault().map(|fault| fault.kind), Some(FaultKind::InvalidKey));
+
+        cpu.set_program_counter(START + 4);
+        run(&mut cpu, 1);
+
+        assert_eq!(cpu.get_program_counter(), START + 6, "{:?}", action);
+    }
+}
+
+#[test]
+fn get_delay_timer() {
+    let mut cpu = cpu_with(&[0xF007]);
+
+    cpu.set_delay_timer(0x20);
+    run(&mut cpu, 1);
+
+    assert_eq!(v(&cpu, 0), 0x20);
+}
+
+#[test]
+fn get_key_press_waits_for_release() {
+    let mut cpu = cpu_with(&[0xF30A]);
+
+    run(&mut cpu, 1);
+    assert_eq!(cpu.get_program_counter(), START);
+
+    cpu.get_keyboard().set_key_pressed(7, true);
+    run(&mut cpu, 1);
+    assert_eq!(cpu.get_program_counter(), START);
+
+    cpu.get_keyboard().set_key_pressed(7, false);
+    run(&mut cpu, 1);
+    assert_eq!(cpu.get_program_counter(), START + 2);
+    assert_eq!(v(&cpu, 3), 7);
+}
+
+#[test]
+fn set_index_long() {
+    let mut cpu = cpu_with(&[0xF000, 0x1234]);
+
+    run(&mut cpu, 1);
+
+    assert_eq!(cpu.get_index(), 0x1234);
+    assert_eq!(cpu.get_program_counter(), START + 4);
+}
+
+#[test]
+fn select_planes() {
+    let mut cpu = cpu_with(&[0xF301]);
+
+    run(&mut cpu, 1);
+
+    assert_eq!(cpu.get_display().get_selected_planes(), 3);
+}
+
+#[test]
+fn load_audio_pattern() {
+    let mut cpu = cpu_with(&[0xA300, 0xF002]);
+    let mut pattern = [0; 16];
+
+    pattern.iter_mut().enumerate().for_each(|(i, sample)| *sample = i as u8);
+    cpu.get_memory_mut()[0x300..0x310].copy_from_slice(&pattern);
+    run(&mut cpu, 2);
+
+    assert_eq!(cpu.get_audio_pattern(), Some(pattern));
+}
+
+#[test]
+fn set_timers() {
+    let mut cpu = cpu_with(&[0x6030, 0x6104, 0xF015, 0xF118]);
+
+    run(&mut cpu, 4);
+    assert_eq!(cpu.get_delay_timer(), 0x30);
+    assert_eq!(cpu.get_sound_timer(), 4);
+    assert!(cpu.is_sound_playing());
+
+    cpu.decrement_timers();
+    assert_eq!(cpu.get_delay_timer(), 0x2F);
+    assert_eq!(cpu.get_sound_timer(), 3);
+}
+
+#[test]
+fn add_to_index() {
+    let mut cpu = cpu_with(&[0xA100, 0x6005, 0xF01E]);
+
+    run(&mut cpu, 3);
+
+    assert_eq!(cpu.get_index(), 0x105);
+}
+
+#[test]
+fn add_to_index_overflow() {
+    let mut cpu = cpu_with(&[0x6002, 0xF01E, 0xF01E]);
+
+    cpu.set_index(0xFFFF);
+    run(&mut cpu, 1);
+    assert_eq!(fault_kind(&mut cpu), FaultKind::IndexOverflow);
+
+    cpu.clear_halt_fault();
+    cpu.set_fault_policy(FaultPolicy::all(FaultAction::Wrap));
+    run(&mut cpu, 1);
+    assert_eq!(cpu.get_index(), 1);
+}
+
+#[test]
+fn set_index_to_character() {
+    let mut cpu = cpu_with(&[0x600A, 0xF029]);
+
+    run(&mut cpu, 2);
+
+    assert_eq!(cpu.get_index(), 50);
+    assert_eq!(&cpu.get_memory()[50..55], &FONT_SPRITES[50..55]);
+}
+
+#[test]
+fn set_index_to_big_character() {
+    let mut cpu = cpu_with(&[0x6003, 0xF030]);
+
+    run(&mut cpu, 2);
+
+    let index = cpu.get_index() as usize;
+
+    assert_eq!(index, FONT_SPRITES.len() + 30);
+    assert_eq!(&cpu.get_memory()[index..index + 10], &BIG_FONT_SPRITES[30..40]);
+}
+
+#[test]
+fn set_pitch() {
+    let mut cpu = cpu_with(&[0x6080, 0xF03A]);
+
+    run(&mut cpu, 2);
+
+    assert_eq!(cpu.get_pitch(), 0x80);
+}
+
+#[test]
+fn store_bcd() {
+    let mut cpu = cpu_with(&[0x60FE, 0xA300, 0xF033]);
+
+    run(&mut cpu, 3);
+
+    assert_eq!(&cpu.get_memory()[0x300..0x303], &[2, 5, 4]);
+}
+
+#[test]
+fn register_dump() {
+    for (quirks, index) in [(Quirks::super_chip(), 0x300), (Quirks::cosmac_vip(), 0x303)].iter() {
+        let mut cpu = cpu_with(&[0x6001, 0x6102, 0x6203, 0xA300, 0xF255]);
+
+        cpu.set_quirks(*quirks);
+        run(&mut cpu, 5);
+
+        assert_eq!(&cpu.get_memory()[0x300..0x304], &[1, 2, 3, 0]);
+        assert_eq!(cpu.get_index(), *index);
+    }
+}
+
+#[test]
+fn register_load() {
+    for (quirks, index) in [(Quirks::super_chip(), 0x300), (Quirks::cosmac_vip(), 0x303)].iter() {
+        let mut cpu = cpu_with(&[0xA300, 0xF265]);
+
+        cpu.set_quirks(*quirks);
+        cpu.get_memory_mut()[0x300..0x304].copy_from_slice(&[1, 2, 3, 4]);
+        run(&mut cpu, 2);
+
+        assert_eq!(&cpu.get_registers()[0..4], &[1, 2, 3, 0]);
+        assert_eq!(cpu.get_index(), *index);
+    }
+}
+
+#[test]
+fn store_and_load_flags() {
+    let mut cpu = cpu_with(&[0x6001, 0x6102, 0x6203, 0xF175, 0x6000, 0x6100, 0x6200, 0xF285]);
+
+    run(&mut cpu, 8);
+
+    assert_eq!(&cpu.get_registers()[0..3], &[1, 2, 0]);
+}
+
+#[test]
+fn unknown_opcode_faults() {
+    let mut cpu = cpu_with(&[0xFFFF]);
+
+    assert_eq!(fault_kind(&mut cpu), FaultKind::UnknownOpcode);
+}
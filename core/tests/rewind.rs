@@ -0,0 +1,106 @@
+//! Checks that the rewind buffer gives back every state it was given, newest first.
+
+use ch8_core::rewind::RewindBuffer;
+
+/// Makes a state of `len` zeros with a few bytes set, like a machine where little changed.
+fn state_with(len: usize, changes: &[(usize, u8)]) -> Vec<u8> {
+    let mut state = vec![0; len];
+
+    for &(pos, val) in changes {
+        state[pos] = val;
+    }
+
+    state
+}
+
+#[test]
+fn states_come_back_newest_first() {
+    let states: Vec<Vec<u8>> = (0..10u8)
+        .map(|frame| state_with(64, &[(0, frame), (frame as usize, 0xFF), (63, frame * 3)]))
+        .collect();
+    let mut rewind = RewindBuffer::new(20);
+
+    for state in states.iter() {
+        rewind.push(state.clone());
+    }
+
+    assert_eq!(rewind.len(), 10);
+
+    for state in states.iter().rev() {
+        assert_eq!(rewind.pop().as_ref(), Some(state));
+    }
+
+    assert!(rewind.is_empty());
+}
+
+#[test]
+fn runs_longer_than_the_run_limit_round_trip() {
+    // zero runs and literal runs both need splitting at 65535 bytes
+    let len = 200_000;
+    let states = [
+        state_with(len, &[]),
+        state_with(len, &[(0, 1), (len - 1, 2)]),
+        (0..len).map(|i| (i % 255) as u8 + 1).collect(),
+        state_with(len, &[(70_000, 3), (140_000, 4)]),
+        state_with(len, &[(len - 1, 5)])
+    ];
+    let mut rewind = RewindBuffer::new(10);
+
+    for state in states.iter() {
+        rewind.push(state.clone());
+    }
+
+    for state in states.iter().rev() {
+        assert_eq!(rewind.pop().as_ref(), Some(state));
+    }
+}
+
+#[test]
+fn states_of_different_lengths_round_trip() {
+    let states = [state_with(16, &[(3, 1)]), state_with(32, &[(3, 1)]), state_with(16, &[(5, 2)])];
+    let mut rewind = RewindBuffer::new(10);
+
+    for state in states.iter() {
+        rewind.push(state.clone());
+    }
+
+    for state in states.iter().rev() {
+        assert_eq!(rewind.pop().as_ref(), Some(state));
+    }
+}
+
+#[test]
+fn rewinding_stops_at_the_oldest_kept_state() {
+    let mut rewind = RewindBuffer::new(3);
+
+    for frame in 0..5 {
+        rewind.push(state_with(8, &[(frame, 0xAA)]));
+    }
+
+    // only the newest 3 are kept
+    assert_eq!(rewind.len(), 3);
+
+    for frame in (2..5).rev() {
+        assert_eq!(rewind.pop(), Some(state_with(8, &[(frame, 0xAA)])));
+    }
+
+    assert_eq!(rewind.pop(), None);
+    assert_eq!(rewind.pop(), None);
+    assert_eq!(rewind.len(), 0);
+
+    // and it carries on after running dry
+    rewind.push(state_with(8, &[]));
+
+    assert_eq!(rewind.pop(), Some(state_with(8, &[])));
+}
+
+#[test]
+fn unchanged_frames_take_little_memory() {
+    let mut rewind = RewindBuffer::new(100);
+
+    for frame in 0..100 {
+        rewind.push(state_with(4096, &[(100, frame)]));
+    }
+
+    assert!(rewind.get_memory_usage() < 4096 + 100 * 16, "{} bytes", rewind.get_memory_usage());
+}
@@ -0,0 +1,106 @@
+//! Checks that save states restore the whole machine, and that damaged ones are refused.
+
+use ch8_core::cpu::Cpu;
+use ch8_core::savestate::{SaveStateError, VERSION};
+
+const PATTERN: [u8; 16] = [0xF0; 16];
+
+/// Runs a program that leaves something in every part of the machine: registers, the stack, the
+/// display, both timers and an audio pattern with a pitch.
+fn running_cpu() -> Cpu {
+    let mut cpu = Cpu::with_program(&[
+        0x60, 0x2A, // V0 = 42
+        0x61, 0x05, // V1 = 5
+        0xA0, 0x00, // I = the 0 sprite
+        0xD0, 0x15, // draw it at 42, 5
+        0x62, 0x14, // V2 = 20
+        0xF2, 0x15, // delay timer = V2
+        0xF2, 0x18, // sound timer = V2
+        0xA3, 0x00, // I = 0x300
+        0xF0, 0x02, // load the audio pattern from I
+        0x63, 0x40, // V3 = 64
+        0xF3, 0x3A, // pitch = V3
+        0x22, 0x18, // call 0x218
+        0x12, 0x18 // 0x218: loop forever
+    ]);
+
+    cpu.get_memory_mut()[0x300..0x310].copy_from_slice(&PATTERN);
+
+    for _ in 0..3 {
+        cpu.run_frame().unwrap();
+    }
+
+    cpu
+}
+
+#[test]
+fn round_trip_restores_the_machine() {
+    let original = running_cpu();
+    let state = original.save_state();
+    let mut restored = Cpu::new();
+
+    restored.load_state(&state).unwrap();
+
+    assert_eq!(restored.get_program_counter(), 0x218);
+    assert_eq!(restored.get_index(), 0x300);
+    assert_eq!(&restored.get_registers()[0..4], &[42, 5, 20, 64]);
+    assert_eq!(restored.get_stack(), original.get_stack());
+    assert_eq!(restored.get_delay_timer(), 17);
+    assert_eq!(restored.get_sound_timer(), 17);
+    assert_eq!(restored.get_memory(), original.get_memory());
+    assert!(restored.get_display().is_set_at(42, 5));
+    assert_eq!(restored.get_display().get_color_at(42, 5), original.get_display().get_color_at(42, 5));
+    assert_eq!(restored.get_audio_pattern(), Some(PATTERN));
+    assert_eq!(restored.get_pitch(), 64);
+
+    // nothing is lost, so saving again gives the same bytes
+    assert_eq!(restored.save_state(), state);
+}
+
+#[test]
+fn restored_machine_carries_on_the_same() {
+    let mut original = running_cpu();
+    let mut restored = Cpu::new();
+
+    restored.load_state(&original.save_state()).unwrap();
+
+    for _ in 0..10 {
+        original.run_frame().unwrap();
+        restored.run_frame().unwrap();
+    }
+
+    assert_eq!(restored.save_state(), original.save_state());
+}
+
+#[test]
+fn wrong_magic_is_refused() {
+    let mut state = running_cpu().save_state();
+
+    state[0] = b'X';
+
+    assert_eq!(Cpu::new().load_state(&state), Err(SaveStateError::BadMagic));
+}
+
+#[test]
+fn wrong_version_is_refused() {
+    let mut state = running_cpu().save_state();
+
+    state[4] = VERSION + 1;
+
+    assert_eq!(Cpu::new().load_state(&state), Err(SaveStateError::UnsupportedVersion(VERSION + 1)));
+}
+
+#[test]
+fn truncated_state_is_refused_and_leaves_the_cpu_alone() {
+    let state = running_cpu().save_state();
+    let mut cpu = Cpu::with_program(&[0x12, 0x00]);
+    let before = cpu.save_state();
+
+    for len in 0..state.len() {
+        let expected = if len < 4 { SaveStateError::BadMagic } else { SaveStateError::Truncated };
+
+        assert_eq!(cpu.load_state(&state[..len]), Err(expected), "{} bytes", len);
+    }
+
+    assert_eq!(cpu.save_state(), before);
+}
@@ -0,0 +1,106 @@
+//! Checks the JSON Lines trace output and the address range and opcode kind filters.
+
+use ch8_core::cpu::Cpu;
+use ch8_core::trace::{TraceFilter, Tracer};
+
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+/// A writer that can still be read after it's handed to a tracer.
+#[derive(Clone, Default)]
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Runs a few instructions with a filter and returns the trace lines.
+fn trace(filter: TraceFilter) -> Vec<String> {
+    let mut cpu = Cpu::with_program(&[
+        0x60, 0x05, // 0x200: V0 = 5
+        0xA0, 0x00, // 0x202: I = 0
+        0xD0, 0x05, // 0x204: draw
+        0x70, 0x01, // 0x206: V0 += 1
+        0xD0, 0x05, // 0x208: draw
+        0x12, 0x0A // 0x20A: loop forever
+    ]);
+    let buffer = SharedBuffer::default();
+
+    cpu.set_tracer(Some(Tracer::new(Box::new(buffer.clone()), filter)));
+    cpu.run_cycles(6).unwrap();
+    cpu.take_tracer().unwrap().finish().unwrap();
+
+    let text = String::from_utf8(buffer.0.borrow().clone()).unwrap();
+
+    text.lines().map(String::from).collect()
+}
+
+/// Gets the `pc` field of each line.
+fn addresses(lines: &[String]) -> Vec<&str> {
+    lines.iter().map(|line| &line[7..12]).collect()
+}
+
+#[test]
+fn every_instruction_is_written_as_json() {
+    let lines = trace(TraceFilter::default());
+
+    assert_eq!(lines.len(), 6);
+    assert_eq!(
+        lines[2],
+        "{\"pc\":\"0x204\",\"word\":\"0xD005\",\"op\":\"DrawSprite\",\"args\":[0,0,5],\
+         \"v\":[5,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0],\"i\":\"0x000\",\"dt\":0,\"st\":0}"
+    );
+    assert!(lines[0].contains("\"op\":\"SetVal\",\"args\":[0,5]"));
+    assert!(lines[5].contains("\"op\":\"Jump\",\"args\":[522]"));
+}
+
+#[test]
+fn address_range_filter_is_inclusive() {
+    let lines = trace(TraceFilter {
+        address_range: Some(0x202..=0x206),
+        ..TraceFilter::default()
+    });
+
+    assert_eq!(addresses(&lines), vec!["0x202", "0x204", "0x206"]);
+}
+
+#[test]
+fn kind_filter_matches_opcode_names() {
+    let lines = trace(TraceFilter {
+        opcode_kinds: Some(vec![String::from("DrawSprite"), String::from("Jump")]),
+        ..TraceFilter::default()
+    });
+
+    assert_eq!(addresses(&lines), vec!["0x204", "0x208", "0x20A"]);
+}
+
+#[test]
+fn both_filters_must_match() {
+    let lines = trace(TraceFilter {
+        address_range: Some(0x206..=0x20A),
+        opcode_kinds: Some(vec![String::from("DrawSprite")])
+    });
+
+    assert_eq!(addresses(&lines), vec!["0x208"]);
+}
+
+#[test]
+fn unknown_words_match_the_unknown_kind() {
+    let filter = TraceFilter {
+        opcode_kinds: Some(vec![String::from("Unknown")]),
+        ..TraceFilter::default()
+    };
+
+    assert!(filter.matches(0x200, "Unknown"));
+    assert!(!filter.matches(0x200, "DrawSprite"));
+    assert!(TraceFilter::default().matches(0xFFF, "Unknown"));
+}
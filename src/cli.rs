@@ -0,0 +1,214 @@
+use ch8_core::audio::{self, Waveform};
+use ch8_core::quirks::{self, Quirks};
+use ch8_core::trace::TraceFilter;
+use crate::config::Settings;
+use crate::palette::Palette;
+
+use std::path::PathBuf;
+use std::process;
+use std::time::Duration;
+
+pub const USAGE: &str = "\
+Usage: ch8-rs ROM [options]
+
+Emulation:
+  --ipf N              instructions per frame, 60 frames a second (default 8, 480 a second)
+  --preset NAME        quirks preset: vip, chip48, schip or modern (default none, with no quirks)
+  --load-address ADDR  hex address to load the ROM at and start from (default 200, 600 for ETI-660)
+  --seed N             seed for the random number generator, for repeatable runs
+  --config FILE        settings file to use instead of the one in the user's config folder
+
+Display:
+  --scale N            window size as a multiple of 64x32 (default 15)
+  --palette COLOURS    default, amber, green or lcd, or 2 or 4 hex colours like 000000,FFFFFF
+
+Sound:
+  --mute               don't play any sound
+  --waveform NAME      shape of the beep: square, sine or triangle (default sine)
+  --frequency HZ       frequency of the beep (default 440)
+  --volume N           volume from 0 to 1 (default 1)
+  --ramp MS            milliseconds to fade the sound in and out over (default 5)
+  --wav FILE           record the sound to a WAV file
+
+Debugging:
+  --paused             start paused at the debugger prompt
+  --tui                run in the terminal with a debugger view instead of a window
+  --gdb PORT           wait for gdb to attach on a port, e.g. 1234
+  --trace FILE         write every instruction executed to a file as JSON Lines
+  --trace-range S-E    only trace addresses in a hex range, e.g. 200-2FF
+  --trace-ops KINDS    only trace these opcode kinds, e.g. DrawSprite,Call
+
+  -h, --help           show this message
+  -V, --version        show the version";
+
+/// Settings from the command line. Anything left out is `None` so defaults can be filled in later.
+pub struct Options {
+    pub rom: PathBuf,
+    pub instructions_per_frame: Option<u32>,
+    pub preset: Option<String>,
+    pub load_address: Option<usize>,
+    pub seed: Option<u64>,
+    pub config: Option<PathBuf>,
+    pub scale: Option<u32>,
+    pub palette: Option<Palette>,
+    pub mute: bool,
+    pub waveform: Option<Waveform>,
+    pub frequency: Option<f32>,
+    pub volume: Option<f32>,
+    pub ramp: Option<Duration>,
+    pub wav: Option<PathBuf>,
+    pub start_paused: bool,
+    pub tui: bool,
+    pub gdb_port: Option<u16>,
+    pub trace: Option<PathBuf>,
+    pub trace_filter: TraceFilter
+}
+
+/// Parses the command line, without the program name. Prints the help or version and exits if
+/// asked for them.
+pub fn parse_args(args: Vec<String>) -> Result<Options, String> {
+    let mut rom = None;
+    let mut parsed = Options {
+        rom: PathBuf::new(),
+        instructions_per_frame: None,
+        preset: None,
+        load_address: None,
+        seed: None,
+        config: None,
+        scale: None,
+        palette: None,
+        mute: false,
+        waveform: None,
+        frequency: None,
+        volume: None,
+        ramp: None,
+        wav: None,
+        start_paused: false,
+        tui: false,
+        gdb_port: None,
+        trace: None,
+        trace_filter: TraceFilter::default()
+    };
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("{} needs a value", name));
+
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            "-V" | "--version" => {
+                println!("ch8-rs {}", env!("CARGO_PKG_VERSION"));
+                process::exit(0);
+            }
+            "--ipf" => {
+                parsed.instructions_per_frame = Some(
+                    value("--ipf")?
+                        .parse()
+                        .ok()
+                        .filter(|&ipf| ipf > 0)
+                        .ok_or("--ipf must be a number above 0")?
+                );
+            }
+            "--preset" => parsed.preset = Some(parse_preset(&value("--preset")?)?),
+            "--load-address" => parsed.load_address = Some(parse_hex(&value("--load-address")?)?),
+            "--seed" => parsed.seed = Some(value("--seed")?.parse().map_err(|_| "--seed must be a number")?),
+            "--config" => parsed.config = Some(PathBuf::from(value("--config")?)),
+            "--scale" => {
+                parsed.scale = Some(
+                    value("--scale")?
+                        .parse()
+                        .ok()
+                        .filter(|&scale| scale > 0)
+                        .ok_or("--scale must be a number above 0")?
+                );
+            }
+            "--palette" => parsed.palette = Some(Palette::parse(&value("--palette")?)?),
+            "--mute" => parsed.mute = true,
+            "--waveform" => parsed.waveform = Some(parse_waveform(&value("--waveform")?)?),
+            "--frequency" => parsed.frequency = Some(parse_frequency(&value("--frequency")?)?),
+            "--volume" => parsed.volume = Some(parse_volume(&value("--volume")?)?),
+            "--ramp" => {
+                let millis = value("--ramp")?.parse().map_err(|_| "--ramp must be a number of milliseconds")?;
+
+                parsed.ramp = Some(Duration::from_millis(millis));
+            }
+            "--wav" => parsed.wav = Some(PathBuf::from(value("--wav")?)),
+            "--paused" => parsed.start_paused = true,
+            "--tui" => parsed.tui = true,
+            "--gdb" => parsed.gdb_port = Some(value("--gdb")?.parse().map_err(|_| "--gdb must be a port number")?),
+            "--trace" => parsed.trace = Some(PathBuf::from(value("--trace")?)),
+            "--trace-range" => {
+                let range = value("--trace-range")?;
+                let (start, end) = range.split_once('-').ok_or("--trace-range must look like 200-2FF")?;
+
+                parsed.trace_filter.address_range = Some(parse_hex(start)?..=parse_hex(end)?);
+            }
+            "--trace-ops" => {
+                let kinds = value("--trace-ops")?;
+
+                parsed.trace_filter.opcode_kinds = Some(kinds.split(',').map(|kind| kind.trim().to_string()).collect());
+            }
+            _ if arg.starts_with('-') => return Err(format!("Unknown option {}", arg)),
+            _ if rom.is_none() => rom = Some(arg),
+            _ => return Err(format!("Unexpected argument {}", arg))
+        }
+    }
+
+    parsed.rom = PathBuf::from(rom.ok_or("You must specify the ROM file to run.")?);
+
+    Ok(parsed)
+}
+
+impl Options {
+    /// Fills in anything not given on the command line from the config file. Muting in either
+    /// place mutes the sound.
+    pub fn fill_from(&mut self, settings: &Settings) -> Result<(), String> {
+        if self.palette.is_none() {
+            self.palette = settings.palette.as_deref().map(Palette::parse).transpose()?;
+        }
+
+        self.instructions_per_frame = self.instructions_per_frame.or(settings.instructions_per_frame);
+        self.preset = self.preset.take().or_else(|| settings.preset.clone());
+        self.scale = self.scale.or(settings.scale);
+        self.mute |= settings.mute.unwrap_or(false);
+        self.waveform = self.waveform.or(settings.waveform);
+        self.frequency = self.frequency.or(settings.frequency);
+        self.volume = self.volume.or(settings.volume);
+        self.ramp = self.ramp.or(settings.ramp);
+
+        Ok(())
+    }
+}
+
+fn parse_preset(name: &str) -> Result<String, String> {
+    match Quirks::from_preset_name(name) {
+        Some(_) => Ok(name.to_string()),
+        None => Err(format!("Unknown preset {}, expected one of: {}", name, quirks::PRESET_NAMES.join(", ")))
+    }
+}
+
+fn parse_waveform(name: &str) -> Result<Waveform, String> {
+    Waveform::from_name(name)
+        .ok_or_else(|| format!("Unknown waveform {}, expected one of: {}", name, audio::WAVEFORM_NAMES.join(", ")))
+}
+
+fn parse_frequency(text: &str) -> Result<f32, String> {
+    text.parse()
+        .ok()
+        .filter(|&frequency: &f32| frequency > 0.0)
+        .ok_or_else(|| format!("{} isn't a frequency in Hz", text))
+}
+
+fn parse_volume(text: &str) -> Result<f32, String> {
+    text.parse()
+        .ok()
+        .filter(|volume| (0.0..=1.0).contains(volume))
+        .ok_or_else(|| format!("{} isn't a volume from 0 to 1", text))
+}
+
+fn parse_hex(text: &str) -> Result<usize, String> {
+    usize::from_str_radix(text.trim_start_matches("0x"), 16).map_err(|_| format!("{} isn't a hex address", text))
+}
@@ -0,0 +1,313 @@
+//! Settings read from a TOML config file, with defaults for every ROM and overrides for
+//! particular ROMs keyed by their SHA-1 hash:
+//!
+//! ```toml
+//! ipf = 10
+//! palette = "amber"
+//!
+//! [keys]
+//! 5 = "Up"
+//!
+//! [rom.5c28a5f85289c9d859f95fd5eadbdcb1c30bb08b]
+//! preset = "vip"
+//! quirks.display_wait = false
+//! ```
+
+use ch8_core::audio::{self, Waveform};
+use ch8_core::database::RomInfo;
+use ch8_core::quirks::{self, Quirks};
+
+use serde::Deserialize;
+
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+/// Settings for running a ROM. Anything not set is `None`, or empty for the lists.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Settings {
+    pub instructions_per_frame: Option<u32>,
+    pub preset: Option<String>,
+    /// Quirks to turn on or off after applying the preset, by their names in [`quirks::QUIRK_NAMES`].
+    pub quirks: Vec<(String, bool)>,
+    /// CHIP-8 keys and the names of the keyboard keys they're bound to.
+    pub keys: Vec<(u8, String)>,
+    /// A palette name or list of colours, as given to `--palette`.
+    pub palette: Option<String>,
+    pub scale: Option<u32>,
+    pub mute: Option<bool>,
+    pub waveform: Option<Waveform>,
+    pub frequency: Option<f32>,
+    pub volume: Option<f32>,
+    pub ramp: Option<Duration>
+}
+
+impl Settings {
+    /// Gets the settings the ROM database says a ROM should run with.
+    pub fn from_rom_info(info: &RomInfo) -> Self {
+        Settings {
+            instructions_per_frame: info.instructions_per_frame,
+            preset: info.platform.get_preset_name().map(String::from),
+            palette: info
+                .colors
+                .map(|[background, foreground]| format!("{:06X},{:06X}", background, foreground)),
+            ..Settings::default()
+        }
+    }
+
+    /// Fills in anything not set from other settings. Quirks and keys from both are kept, with
+    /// these applied last so they take priority.
+    pub fn or(self, fallback: Settings) -> Settings {
+        Settings {
+            instructions_per_frame: self.instructions_per_frame.or(fallback.instructions_per_frame),
+            preset: self.preset.or(fallback.preset),
+            quirks: fallback.quirks.into_iter().chain(self.quirks).collect(),
+            keys: fallback.keys.into_iter().chain(self.keys).collect(),
+            palette: self.palette.or(fallback.palette),
+            scale: self.scale.or(fallback.scale),
+            mute: self.mute.or(fallback.mute),
+            waveform: self.waveform.or(fallback.waveform),
+            frequency: self.frequency.or(fallback.frequency),
+            volume: self.volume.or(fallback.volume),
+            ramp: self.ramp.or(fallback.ramp)
+        }
+    }
+
+    /// Turns the quirks on or off on top of a preset.
+    pub fn apply_quirks(&self, quirks: &mut Quirks) {
+        for (name, enabled) in self.quirks.iter() {
+            quirks.set_by_name(name, *enabled);
+        }
+    }
+}
+
+/// Settings as they're written in the file, before they're checked.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct SettingsTable {
+    ipf: Option<u32>,
+    preset: Option<String>,
+    quirks: BTreeMap<String, bool>,
+    keys: BTreeMap<String, String>,
+    palette: Option<String>,
+    scale: Option<u32>,
+    mute: Option<bool>,
+    waveform: Option<String>,
+    frequency: Option<f32>,
+    volume: Option<f32>,
+    /// Milliseconds.
+    ramp: Option<u64>
+}
+
+impl SettingsTable {
+    /// Checks the values are in range and turns names into what they name.
+    fn check(self) -> Result<Settings, String> {
+        if self.ipf == Some(0) {
+            return Err("ipf must be a number above 0".to_string());
+        }
+
+        if self.scale == Some(0) {
+            return Err("scale must be a number above 0".to_string());
+        }
+
+        if let Some(name) = self.preset.as_deref() {
+            if Quirks::from_preset_name(name).is_none() {
+                return Err(format!("unknown preset {}, expected one of: {}", name, quirks::PRESET_NAMES.join(", ")));
+            }
+        }
+
+        if let Some(name) = self.quirks.keys().find(|name| !quirks::QUIRK_NAMES.contains(&name.as_str())) {
+            return Err(format!("unknown quirk {}, expected one of: {}", name, quirks::QUIRK_NAMES.join(", ")));
+        }
+
+        let waveform = match self.waveform.as_deref() {
+            Some(name) => Some(Waveform::from_name(name).ok_or_else(|| {
+                format!("unknown waveform {}, expected one of: {}", name, audio::WAVEFORM_NAMES.join(", "))
+            })?),
+            None => None
+        };
+
+        if self.frequency.is_some_and(|frequency| frequency <= 0.0) {
+            return Err("frequency must be above 0".to_string());
+        }
+
+        if self.volume.is_some_and(|volume| !(0.0..=1.0).contains(&volume)) {
+            return Err("volume must be from 0 to 1".to_string());
+        }
+
+        let keys = self
+            .keys
+            .into_iter()
+            .map(|(key, name)| {
+                u8::from_str_radix(&key, 16)
+                    .ok()
+                    .filter(|&chip8_key| chip8_key <= 0xF)
+                    .map(|chip8_key| (chip8_key, name))
+                    .ok_or_else(|| format!("{} isn't a CHIP-8 key from 0 to F", key))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Settings {
+            instructions_per_frame: self.ipf,
+            preset: self.preset,
+            quirks: self.quirks.into_iter().collect(),
+            keys,
+            palette: self.palette,
+            scale: self.scale,
+            mute: self.mute,
+            waveform,
+            frequency: self.frequency,
+            volume: self.volume,
+            ramp: self.ramp.map(Duration::from_millis)
+        })
+    }
+}
+
+/// Settings for every ROM, and for particular ROMs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Config {
+    pub defaults: Settings,
+    /// Settings by the lowercase hex SHA-1 hash of the ROM.
+    pub roms: HashMap<String, Settings>
+}
+
+impl Config {
+    /// Parses a config file. The `rom` table holds a section for each ROM, and everything else
+    /// is the defaults.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut table: toml::value::Table = toml::from_str(text).map_err(|err| err.to_string())?;
+        let roms = match table.remove("rom") {
+            Some(toml::Value::Table(roms)) => roms,
+            Some(_) => return Err("rom must be a table of ROM sections".to_string()),
+            None => toml::value::Table::new()
+        };
+
+        Ok(Config {
+            defaults: parse_settings(toml::Value::Table(table))?,
+            roms: roms
+                .into_iter()
+                .map(|(hash, settings)| match parse_settings(settings) {
+                    Ok(settings) => Ok((hash.to_ascii_lowercase(), settings)),
+                    Err(err) => Err(format!("in [rom.{}]: {}", hash, err))
+                })
+                .collect::<Result<_, String>>()?
+        })
+    }
+
+    /// Gets the settings for a ROM by its hash. Anything its section doesn't set comes from the
+    /// defaults, and then from `known`, the settings known to suit the ROM such as from the ROM
+    /// database.
+    pub fn get_settings(&self, rom_hash: &str, known: Settings) -> Settings {
+        let defaults = self.defaults.clone().or(known);
+
+        match self.roms.get(&rom_hash.to_ascii_lowercase()) {
+            Some(settings) => settings.clone().or(defaults),
+            None => defaults
+        }
+    }
+}
+
+fn parse_settings(value: toml::Value) -> Result<Settings, String> {
+    value.try_into::<SettingsTable>().map_err(|err| err.to_string())?.check()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PONG_HASH: &str = "b232ef880bd6060fb45fa6effed7edf0ae95670e";
+
+    #[test]
+    fn rom_sections_override_defaults() {
+        let config = Config::parse(&format!(
+            r#"
+            # used for every ROM
+            ipf = 10
+            palette = "amber"   # a comment after a value
+            waveform = 'square'
+            volume = 0.5
+
+            [keys]
+            5 = "Up"
+
+            [rom.{}]
+            ipf = 20
+            preset = "vip"
+            ramp = 0
+            quirks.display_wait = false
+            keys.5 = "Space"
+            "#,
+            PONG_HASH.to_uppercase()
+        ))
+        .unwrap();
+
+        let pong = config.get_settings(PONG_HASH, Settings::default());
+
+        assert_eq!(pong.instructions_per_frame, Some(20));
+        assert_eq!(pong.preset.as_deref(), Some("vip"));
+        assert_eq!(pong.palette.as_deref(), Some("amber"));
+        assert_eq!(pong.waveform, Some(Waveform::Square));
+        assert_eq!(pong.volume, Some(0.5));
+        assert_eq!(pong.ramp, Some(Duration::from_millis(0)));
+        // the ROM's binding is applied last
+        assert_eq!(pong.keys, vec![(5, "Up".to_string()), (5, "Space".to_string())]);
+
+        let mut quirks = Quirks::cosmac_vip();
+
+        pong.apply_quirks(&mut quirks);
+
+        assert!(!quirks.display_wait);
+        assert!(quirks.shift_uses_vy);
+
+        let other = config.get_settings("da39a3ee5e6b4b0d3255bfef95601890afd80709", Settings::default());
+
+        assert_eq!(other.instructions_per_frame, Some(10));
+        assert_eq!(other.preset, None);
+        assert!(other.quirks.is_empty());
+    }
+
+    #[test]
+    fn errors_name_the_setting() {
+        let error = |text: &str| Config::parse(text).unwrap_err();
+
+        assert_eq!(error("ipf = 0"), "ipf must be a number above 0");
+        assert!(error("speed = 10").starts_with("unknown field `speed`"));
+        assert_eq!(error("preset = \"dos\""), "unknown preset dos, expected one of: vip, chip48, schip, modern");
+        assert_eq!(error("[keys]\nG = \"Up\""), "G isn't a CHIP-8 key from 0 to F");
+        assert_eq!(error("[rom.abc]\n[rom.abc.quirks]\nwrap = true"), "in [rom.abc]: unknown quirk wrap, expected one \
+            of: shift_uses_vy, load_store_increments_index, jump_uses_vx, logic_resets_vf, clip_sprites, display_wait");
+        assert!(error("palette = \"amber").contains("line 1"));
+    }
+
+    #[test]
+    fn database_settings_sit_below_the_config() {
+        let invaders = ch8_core::database::find("5c28a5f85289c9d859f95fd5eadbdcb1c30bb08b").unwrap();
+        let known = Settings {
+            palette: Some("amber".to_string()),
+            ..Settings::from_rom_info(invaders)
+        };
+        let config = Config::parse(&format!(
+            r#"
+            preset = "schip"
+            ipf = 12
+
+            [rom.{}]
+            ipf = 20
+            "#,
+            invaders.sha1
+        ))
+        .unwrap();
+
+        let settings = config.get_settings(invaders.sha1, known.clone());
+
+        // the ROM's own section beats the defaults, which beat the database
+        assert_eq!(settings.instructions_per_frame, Some(20));
+        assert_eq!(settings.preset.as_deref(), Some("schip"));
+        assert_eq!(settings.palette.as_deref(), Some("amber"));
+
+        let settings = Config::default().get_settings(invaders.sha1, known);
+
+        // a modern CHIP-8 ROM runs without a preset unless the config gives one
+        assert_eq!(settings.preset, None);
+        assert_eq!(settings.palette.as_deref(), Some("amber"));
+    }
+}
@@ -0,0 +1,312 @@
+use ch8_core::cpu::Cpu;
+use ch8_core::debugger::{self, Condition, Debugger, Register, StopReason, WatchKind, Watchpoint};
+use ch8_core::disassembler;
+
+use std::convert::TryFrom;
+use std::io::{self, Write};
+
+const HELP: &str = "\
+Commands:
+  c, continue              resume running
+  s, step [n]              execute n instructions (default 1)
+  n, next                  step over a subroutine call
+  f, finish                run until the current subroutine returns
+  r, regs                  show registers, timers and the stack
+  l, list [addr] [n]       disassemble n instructions (default at the PC)
+  m, mem addr [len]        dump memory
+  w, write addr byte..     write bytes to memory
+  set reg value            set v0-vf, i, pc, dt or st
+  b, break addr            add a breakpoint
+  d, delete addr           remove breakpoints and watchpoints at an address
+  watch addr[-end] r|w|rw  break when memory is read or written
+  cond reg op value        break when e.g. `v3 == 5` becomes true (== != < <= > >=)
+  uncond n                 remove a condition by number
+  break-op Kind            break before an opcode kind, e.g. DrawSprite
+  unbreak-op Kind          stop breaking on an opcode kind
+  collision on|off         break after a sprite draw collides
+  info                     list breakpoints, watchpoints and conditions
+  h, help                  show this message
+Numbers are hex, with or without 0x.";
+
+/// Reads debugger commands from the terminal while the interpreter is paused.
+pub struct DebugPrompt {
+    last_command: String
+}
+
+impl DebugPrompt {
+    pub fn new() -> Self {
+        DebugPrompt {
+            last_command: String::new()
+        }
+    }
+
+    /// Reads commands until one of them runs the CPU. Returns true if execution should stay paused,
+    /// so the caller can redraw before prompting again.
+    pub fn prompt(&mut self, cpu: &mut Cpu, debugger: &mut Debugger) -> bool {
+        loop {
+            print!("(ch8) ");
+            io::stdout().flush().ok();
+
+            let mut line = String::new();
+
+            // carry on running if stdin is closed, rather than spinning on an empty prompt
+            match io::stdin().read_line(&mut line) {
+                Ok(0) | Err(_) => return false,
+                Ok(_) => {}
+            }
+
+            // an empty line repeats the last command, like gdb
+            let line = line.trim();
+            let line = if line.is_empty() { self.last_command.clone() } else { line.to_string() };
+
+            self.last_command = line.clone();
+
+            let args: Vec<&str> = line.split_whitespace().collect();
+
+            match args.first().copied() {
+                None => {}
+                Some("c") | Some("continue") => return false,
+                Some("s") | Some("step") => {
+                    let count = args.get(1).map_or(Some(1), |n| n.parse().ok());
+
+                    match count {
+                        Some(count) => {
+                            let mut reason = StopReason::Step;
+
+                            for _ in 0..count {
+                                reason = debugger.step(cpu);
+
+                                if reason != StopReason::Step {
+                                    break;
+                                }
+                            }
+
+                            report(cpu, &reason);
+                            return true;
+                        }
+                        None => println!("Usage: step [n]")
+                    }
+                }
+                Some("n") | Some("next") => {
+                    let reason = debugger.step_over(cpu, debugger::DEFAULT_STEP_LIMIT);
+
+                    report(cpu, &reason);
+                    return true;
+                }
+                Some("f") | Some("finish") => {
+                    let reason = debugger.step_out(cpu, debugger::DEFAULT_STEP_LIMIT);
+
+                    report(cpu, &reason);
+                    return true;
+                }
+                Some(command) => {
+                    if let Err(err) = run_command(cpu, debugger, command, &args[1..]) {
+                        println!("{}", err);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Default for DebugPrompt {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Prints why execution stopped and the instruction it stopped at.
+pub fn report(cpu: &Cpu, reason: &StopReason) {
+    if *reason != StopReason::Step {
+        println!("Stopped: {}", reason);
+    }
+
+    print_listing(cpu, cpu.get_program_counter(), 1);
+}
+
+/// Runs a command that inspects or changes state without executing anything.
+fn run_command(cpu: &mut Cpu, debugger: &mut Debugger, command: &str, args: &[&str]) -> Result<(), String> {
+    match command {
+        "h" | "help" => println!("{}", HELP),
+        "r" | "regs" => print_registers(cpu),
+        "l" | "list" => {
+            let addr = args.first().map_or(Ok(cpu.get_program_counter()), |arg| parse_number(arg))?;
+            let count = args.get(1).map_or(Ok(10), |arg| parse_number(arg))?;
+
+            print_listing(cpu, addr, count);
+        }
+        "m" | "mem" => {
+            let addr = parse_number(args.first().ok_or("Usage: mem addr [len]")?)?;
+            let len = args.get(1).map_or(Ok(0x40), |arg| parse_number(arg))?;
+
+            print_memory(cpu, addr, len);
+        }
+        "w" | "write" => {
+            let addr = parse_number(args.first().ok_or("Usage: write addr byte..")?)?;
+            let bytes = args[1..]
+                .iter()
+                .map(|arg| parse_number(arg).and_then(|byte| u8::try_from(byte).map_err(|_| format!("{} isn't a byte", arg))))
+                .collect::<Result<Vec<u8>, String>>()?;
+            let memory = cpu.get_memory_mut();
+            let size = memory.len();
+
+            for (i, byte) in bytes.into_iter().enumerate() {
+                memory[(addr + i) % size] = byte;
+            }
+        }
+        "set" => {
+            let (name, value) = match args {
+                [name, value] => (*name, parse_number(value)?),
+                _ => return Err(String::from("Usage: set reg value"))
+            };
+
+            if name.eq_ignore_ascii_case("pc") {
+                cpu.set_program_counter(value);
+                // moving the PC is how you get past a fault
+                cpu.clear_halt_fault();
+            } else {
+                let register = Register::parse(name).ok_or_else(|| format!("Unknown register {}", name))?;
+
+                register.write(cpu, value as u16);
+            }
+        }
+        "b" | "break" => {
+            let addr = parse_number(args.first().ok_or("Usage: break addr")?)?;
+
+            if debugger.add_breakpoint(addr) {
+                println!("Breakpoint at 0x{:03X}", addr);
+            }
+        }
+        "d" | "delete" => {
+            let addr = parse_number(args.first().ok_or("Usage: delete addr")?)?;
+            let removed = debugger.remove_breakpoint(addr) as usize + debugger.remove_watchpoints_at(addr);
+
+            println!("Removed {} at 0x{:03X}", removed, addr);
+        }
+        "watch" => {
+            let (range, kind) = match args {
+                [range, kind] => (*range, *kind),
+                _ => return Err(String::from("Usage: watch addr[-end] r|w|rw"))
+            };
+            let range = match range.split_once('-') {
+                Some((start, end)) => parse_number(start)?..=parse_number(end)?,
+                None => parse_number(range)?..=parse_number(range)?
+            };
+            let kind = match kind {
+                "r" => WatchKind::Read,
+                "w" => WatchKind::Write,
+                "rw" => WatchKind::ReadWrite,
+                _ => return Err(format!("Unknown watch kind {}, expected r, w or rw", kind))
+            };
+
+            debugger.add_watchpoint(Watchpoint {
+                range,
+                kind
+            });
+        }
+        "cond" => {
+            let condition = Condition::parse(&args.join(" ")).ok_or("Usage: cond reg op value, e.g. cond v3 == 5")?;
+
+            debugger.add_condition(condition);
+        }
+        "uncond" => {
+            let index = args.first().and_then(|arg| arg.parse().ok()).ok_or("Usage: uncond n")?;
+
+            debugger.remove_condition(index).ok_or_else(|| format!("No condition {}", index))?;
+        }
+        "break-op" => debugger.add_opcode_break(args.first().ok_or("Usage: break-op Kind")?),
+        "unbreak-op" => {
+            debugger.remove_opcode_break(args.first().ok_or("Usage: unbreak-op Kind")?);
+        }
+        "collision" => match args.first().copied() {
+            Some("on") => debugger.set_break_on_collision(true),
+            Some("off") => debugger.set_break_on_collision(false),
+            _ => return Err(String::from("Usage: collision on|off"))
+        },
+        "info" => print_info(debugger),
+        _ => return Err(format!("Unknown command {}, try help", command))
+    }
+
+    Ok(())
+}
+
+/// Parses a hex number, with or without a `0x` prefix.
+fn parse_number(text: &str) -> Result<usize, String> {
+    let digits = text.trim_start_matches("0x").trim_start_matches("0X");
+
+    usize::from_str_radix(digits, 16).map_err(|_| format!("{} isn't a hex number", text))
+}
+
+fn print_registers(cpu: &Cpu) {
+    for (i, val) in cpu.get_registers().iter().enumerate() {
+        print!("V{:X}={:02X}{}", i, val, if i % 8 == 7 { "\n" } else { " " });
+    }
+
+    println!(
+        "PC={:03X} I={:03X} DT={:02X} ST={:02X}",
+        cpu.get_program_counter(),
+        cpu.get_index(),
+        cpu.get_delay_timer(),
+        cpu.get_sound_timer()
+    );
+
+    let stack: Vec<String> = cpu.get_stack().iter().map(|addr| format!("{:03X}", addr)).collect();
+
+    println!("Stack: [{}]", stack.join(" "));
+
+    if let Some(fault) = cpu.get_halt_fault() {
+        println!("Halted: {}", fault);
+    }
+}
+
+fn print_listing(cpu: &Cpu, addr: usize, count: usize) {
+    for i in 0..count {
+        let addr = addr + i * 2;
+        let word = debugger::read_word(cpu, addr);
+        let marker = if addr == cpu.get_program_counter() { '>' } else { ' ' };
+
+        match disassembler::disassemble_word(word) {
+            Some(opcode) => println!("{} {:03X}: {:04X}  {:?}", marker, addr, word, opcode),
+            None => println!("{} {:03X}: {:04X}  ???", marker, addr, word)
+        }
+    }
+}
+
+fn print_memory(cpu: &Cpu, addr: usize, len: usize) {
+    let memory = cpu.get_memory();
+
+    for row in (0..len).step_by(16) {
+        let bytes: Vec<String> = (row..len.min(row + 16))
+            .map(|i| format!("{:02X}", memory[(addr + i) % memory.len()]))
+            .collect();
+
+        println!("{:03X}: {}", addr + row, bytes.join(" "));
+    }
+}
+
+fn print_info(debugger: &Debugger) {
+    for addr in debugger.get_breakpoints() {
+        println!("Breakpoint at 0x{:03X}", addr);
+    }
+
+    for watchpoint in debugger.get_watchpoints() {
+        println!(
+            "{:?} watchpoint at 0x{:03X}-0x{:03X}",
+            watchpoint.kind,
+            watchpoint.range.start(),
+            watchpoint.range.end()
+        );
+    }
+
+    for (i, condition) in debugger.get_conditions().iter().enumerate() {
+        println!("Condition {}: {}", i, condition);
+    }
+
+    for kind in debugger.get_opcode_breaks() {
+        println!("Break on {}", kind);
+    }
+
+    if debugger.is_breaking_on_collision() {
+        println!("Break on sprite collision");
+    }
+}
@@ -1,50 +1,6 @@
 use winit::event::VirtualKeyCode;
 
-pub struct KeyboardInput {
-    key_states: [bool; 16],
-    last_released_key: Option<u8>,
-    track_next_released_key: bool
-}
-
-impl KeyboardInput {
-    pub fn new() -> Self {
-        KeyboardInput {
-            key_states: [false; 16],
-            last_released_key: None,
-            track_next_released_key: false
-        }
-    }
-
-    /// Checks if we're tracking the next key release
-    pub fn is_tracking_next_key_release(&self) -> bool {
-        self.track_next_released_key
-    }
-
-    /// Tracks for the next key
-    pub fn track_next_key_release(&mut self, track: bool) {
-        self.last_released_key = None;
-        self.track_next_released_key = track;
-    }
-
-    /// Gets the last released key if we're waiting for it
-    pub fn get_last_released_key(&self) -> Option<u8> {
-        self.last_released_key
-    }
-
-    /// Sets a key state
-    pub fn set_key_pressed(&mut self, key: u8, pressed: bool) {
-        self.key_states[key as usize] = pressed;
-
-        if !pressed && self.track_next_released_key {
-            self.last_released_key = Some(key);
-        }
-    }
-
-    /// Checks if a key is currently pressed
-    pub fn is_key_pressed(&self, key: u8) -> bool {
-        self.key_states[key as usize]
-    }
-}
+use crate::window::HOTKEYS;
 
 /// Gets a virtual key code from a CHIP-8 key
 pub fn get_keycode_from_key(key: u8) -> Option<VirtualKeyCode> {
@@ -75,3 +31,176 @@ pub fn get_keycode_from_key(key: u8) -> Option<VirtualKeyCode> {
         _ => None
     }
 }
+
+/// Gets the CHIP-8 key typed as a character, using the same layout as [`get_keycode_from_key`]
+pub fn get_key_from_char(c: char) -> Option<u8> {
+    match c.to_ascii_lowercase() {
+        'x' => Some(0x0),
+        '1' => Some(0x1),
+        '2' => Some(0x2),
+        '3' => Some(0x3),
+        'q' => Some(0x4),
+        'w' => Some(0x5),
+        'e' => Some(0x6),
+        'a' => Some(0x7),
+        's' => Some(0x8),
+        'd' => Some(0x9),
+        'z' => Some(0xA),
+        'c' => Some(0xB),
+        '4' => Some(0xC),
+        'r' => Some(0xD),
+        'f' => Some(0xE),
+        'v' => Some(0xF),
+        _ => None
+    }
+}
+
+/// Keyboard keys that can be bound to CHIP-8 keys by name, matched ignoring case.
+const KEY_NAMES: [(&str, VirtualKeyCode); 64] = [
+    ("0", VirtualKeyCode::Key0),
+    ("1", VirtualKeyCode::Key1),
+    ("2", VirtualKeyCode::Key2),
+    ("3", VirtualKeyCode::Key3),
+    ("4", VirtualKeyCode::Key4),
+    ("5", VirtualKeyCode::Key5),
+    ("6", VirtualKeyCode::Key6),
+    ("7", VirtualKeyCode::Key7),
+    ("8", VirtualKeyCode::Key8),
+    ("9", VirtualKeyCode::Key9),
+    ("A", VirtualKeyCode::A),
+    ("B", VirtualKeyCode::B),
+    ("C", VirtualKeyCode::C),
+    ("D", VirtualKeyCode::D),
+    ("E", VirtualKeyCode::E),
+    ("F", VirtualKeyCode::F),
+    ("G", VirtualKeyCode::G),
+    ("H", VirtualKeyCode::H),
+    ("I", VirtualKeyCode::I),
+    ("J", VirtualKeyCode::J),
+    ("K", VirtualKeyCode::K),
+    ("L", VirtualKeyCode::L),
+    ("M", VirtualKeyCode::M),
+    ("N", VirtualKeyCode::N),
+    ("O", VirtualKeyCode::O),
+    ("P", VirtualKeyCode::P),
+    ("Q", VirtualKeyCode::Q),
+    ("R", VirtualKeyCode::R),
+    ("S", VirtualKeyCode::S),
+    ("T", VirtualKeyCode::T),
+    ("U", VirtualKeyCode::U),
+    ("V", VirtualKeyCode::V),
+    ("W", VirtualKeyCode::W),
+    ("X", VirtualKeyCode::X),
+    ("Y", VirtualKeyCode::Y),
+    ("Z", VirtualKeyCode::Z),
+    ("Up", VirtualKeyCode::Up),
+    ("Down", VirtualKeyCode::Down),
+    ("Left", VirtualKeyCode::Left),
+    ("Right", VirtualKeyCode::Right),
+    ("Space", VirtualKeyCode::Space),
+    ("Enter", VirtualKeyCode::Return),
+    ("Tab", VirtualKeyCode::Tab),
+    ("LShift", VirtualKeyCode::LShift),
+    ("RShift", VirtualKeyCode::RShift),
+    ("LControl", VirtualKeyCode::LControl),
+    ("RControl", VirtualKeyCode::RControl),
+    ("Comma", VirtualKeyCode::Comma),
+    ("Period", VirtualKeyCode::Period),
+    ("Slash", VirtualKeyCode::Slash),
+    ("Semicolon", VirtualKeyCode::Semicolon),
+    ("Apostrophe", VirtualKeyCode::Apostrophe),
+    ("Numpad0", VirtualKeyCode::Numpad0),
+    ("Numpad1", VirtualKeyCode::Numpad1),
+    ("Numpad2", VirtualKeyCode::Numpad2),
+    ("Numpad3", VirtualKeyCode::Numpad3),
+    ("Numpad4", VirtualKeyCode::Numpad4),
+    ("Numpad5", VirtualKeyCode::Numpad5),
+    ("Numpad6", VirtualKeyCode::Numpad6),
+    ("Numpad7", VirtualKeyCode::Numpad7),
+    ("Numpad8", VirtualKeyCode::Numpad8),
+    ("Numpad9", VirtualKeyCode::Numpad9),
+    ("NumpadEnter", VirtualKeyCode::NumpadEnter),
+    ("NumpadAdd", VirtualKeyCode::NumpadAdd)
+];
+
+/// Keyboard keys bound to each CHIP-8 key in the window.
+pub struct KeyMap {
+    keycodes: [VirtualKeyCode; 16]
+}
+
+impl KeyMap {
+    /// Starts from the usual layout and rebinds CHIP-8 keys to keyboard keys by name, e.g.
+    /// `(0x5, "Up")`. Later bindings for the same CHIP-8 key win. Hotkeys can't be bound.
+    pub fn from_bindings(bindings: &[(u8, String)]) -> Result<Self, String> {
+        let mut map = KeyMap::default();
+
+        for (key, name) in bindings.iter() {
+            let keycode = KEY_NAMES
+                .iter()
+                .find(|(key_name, _)| key_name.eq_ignore_ascii_case(name))
+                .map(|&(_, keycode)| keycode)
+                .ok_or_else(|| format!("Unknown key {} bound to CHIP-8 key {:X}", name, key))?;
+
+            if HOTKEYS.contains(&keycode) {
+                return Err(format!("{} is a hotkey, so it can't be bound to CHIP-8 key {:X}", name, key));
+            }
+
+            map.keycodes[*key as usize & 0xF] = keycode;
+        }
+
+        Ok(map)
+    }
+
+    /// Gets the keyboard key bound to a CHIP-8 key.
+    pub fn get_keycode(&self, key: u8) -> VirtualKeyCode {
+        self.keycodes[key as usize & 0xF]
+    }
+
+    /// Gets the name of the keyboard key bound to a CHIP-8 key, to show to the player.
+    pub fn get_key_name(&self, key: u8) -> String {
+        let keycode = self.get_keycode(key);
+
+        match KEY_NAMES.iter().find(|&&(_, named)| named == keycode) {
+            Some((name, _)) => name.to_string(),
+            None => format!("{:?}", keycode)
+        }
+    }
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        let mut keycodes = [VirtualKeyCode::X; 16];
+
+        for (key, keycode) in keycodes.iter_mut().enumerate() {
+            if let Some(default) = get_keycode_from_key(key as u8) {
+                *keycode = default;
+            }
+        }
+
+        KeyMap {
+            keycodes
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bindings_replace_the_usual_keys() {
+        let keys = KeyMap::from_bindings(&[(0x5, "up".to_string()), (0x5, "Space".to_string())]).unwrap();
+
+        assert_eq!(keys.get_keycode(0x5), VirtualKeyCode::Space);
+        assert_eq!(keys.get_keycode(0x4), VirtualKeyCode::Q);
+        assert_eq!(keys.get_key_name(0x5), "Space");
+    }
+
+    #[test]
+    fn hotkeys_and_unknown_keys_are_refused() {
+        let error = |name: &str| KeyMap::from_bindings(&[(0xA, name.to_string())]).err();
+
+        assert_eq!(error("Tab"), Some("Tab is a hotkey, so it can't be bound to CHIP-8 key A".to_string()));
+        assert_eq!(error("F13"), Some("Unknown key F13 bound to CHIP-8 key A".to_string()));
+    }
+}
@@ -1,27 +1,208 @@
-use std::io::prelude::*;
-use std::fs::File;
 use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+use std::process;
 
-mod disassembler;
-mod display;
-mod cpu;
+use ch8_core::audio::BeeperSettings;
+use ch8_core::cpu::{self, Cpu, LoadError};
+use ch8_core::database::{self, RomInfo};
+use ch8_core::gdb::GdbStub;
+use ch8_core::quirks::Quirks;
+use ch8_core::sha1;
+use ch8_core::trace::Tracer;
+use ch8_core::wav::WavFileRecorder;
+
+use crate::cli::Options;
+use crate::config::{Config, Settings};
+use crate::keyboard::KeyMap;
+use crate::window::WindowOptions;
+
+mod cli;
+mod config;
+mod debug_prompt;
 mod window;
 mod keyboard;
+mod palette;
 mod speaker;
+mod speed;
+mod tui;
+
+/// Window size as a multiple of the 64x32 display, unless set with `--scale`.
+const DEFAULT_SCALE: u32 = 15;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    let path = args.get(1).expect("You must specify the ROM file to run.");
+    let options = match cli::parse_args(env::args().skip(1).collect()) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, cli::USAGE);
+            process::exit(1);
+        }
+    };
+
+    if let Err(err) = run(options) {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
+
+fn run(mut options: Options) -> Result<(), String> {
+    let rom = fs::read(&options.rom).map_err(|err| format!("Couldn't read {}: {}", options.rom.display(), err))?;
+
+    let hash = sha1::hex_digest(&rom);
+    let info = database::find(&hash);
+    let known = info.map(Settings::from_rom_info).unwrap_or_default();
+
+    // the command line wins over the ROM's section of the config file, then the defaults in the
+    // config file, then what the ROM database knows about it
+    let settings = load_config(options.config.as_deref())?.get_settings(&hash, known);
+
+    options.fill_from(&settings)?;
 
-    let mut f = File::open(path).unwrap();
-    let mut buffer = Vec::new();
+    let keys = KeyMap::from_bindings(&settings.keys)?;
 
-    f.read_to_end(&mut buffer).unwrap();
+    if let Some(info) = info {
+        print_rom_info(info, &keys);
+    }
 
-    let mut cpu = cpu::Cpu::new();
+    let preset = options.preset.as_deref();
+    let mut quirks = preset.and_then(Quirks::from_preset_name).unwrap_or_default();
+    let mut cpu = Cpu::new();
 
+    settings.apply_quirks(&mut quirks);
     cpu.init();
-    cpu.load_program(&buffer);
+    cpu.set_quirks(quirks);
+    cpu.set_instructions_per_frame(options.instructions_per_frame.unwrap_or(cpu::DEFAULT_INSTRUCTIONS_PER_FRAME));
+
+    if let Some(seed) = options.seed {
+        cpu.seed_rng(seed);
+    }
+
+    let load_address = options.load_address.unwrap_or(cpu::PROGRAM_START);
+
+    for warning in cpu::check_program(&rom) {
+        eprintln!("Warning: {}", warning);
+    }
+
+    if let Err(err) = cpu.load_program_at(&rom, load_address) {
+        let hint = match err {
+            LoadError::TooLarge { .. } if cpu.get_memory_size() < cpu::XO_CHIP_MEMORY_SIZE => {
+                " (XO-CHIP programs can use 64K of memory with --preset modern)"
+            }
+            _ => ""
+        };
+
+        return Err(format!("Couldn't load {}: {}{}", options.rom.display(), err, hint));
+    }
+
+    if let Some(path) = options.trace.as_ref() {
+        let file = File::create(path).map_err(|err| format!("Couldn't create {}: {}", path.display(), err))?;
+
+        cpu.set_tracer(Some(Tracer::new(Box::new(BufWriter::new(file)), options.trace_filter.clone())));
+    }
+
+    let beeper = create_beeper_settings(&options);
+    let wav = match options.wav.as_ref() {
+        Some(path) => Some(
+            WavFileRecorder::create(path, beeper).map_err(|err| format!("Couldn't create {}: {}", path.display(), err))?
+        ),
+        None => None
+    };
+
+    // the terminal frontend works without a graphical display, e.g. over SSH
+    if options.tui {
+        return tui::run_tui(cpu, wav, !options.mute).map_err(|err| format!("Terminal error: {}", err));
+    }
+
+    // the program waits for gdb to attach and continue before it starts
+    let gdb = match options.gdb_port {
+        Some(port) => {
+            let stub = GdbStub::bind(("127.0.0.1", port))
+                .map_err(|err| format!("Couldn't listen for gdb on port {}: {}", port, err))?;
+
+            println!("Waiting for gdb on port {}", port);
+
+            Some(stub)
+        }
+        None => None
+    };
+
+    let title = match info {
+        Some(info) => format!("{} - CHIP-8 Interpreter", info.title),
+        None => "CHIP-8 Interpreter".to_string()
+    };
+
+    window::create_window(cpu, WindowOptions {
+        title,
+        rom_path: options.rom,
+        scale: options.scale.unwrap_or(DEFAULT_SCALE),
+        palette: options.palette.unwrap_or_default(),
+        keys,
+        start_paused: options.start_paused,
+        beeper: if options.mute { None } else { Some(beeper) },
+        gdb,
+        wav
+    });
+
+    Ok(())
+}
+
+/// Prints what the ROM is and what its keys do on the keyboard.
+fn print_rom_info(info: &RomInfo, keys: &KeyMap) {
+    println!("{}", info);
+
+    if !info.keys.is_empty() {
+        let hints: Vec<String> = info
+            .keys
+            .iter()
+            .map(|&(key, action)| format!("{} {}", keys.get_key_name(key), action))
+            .collect();
+
+        println!("Keys: {}", hints.join(", "));
+    }
+}
+
+/// Reads the config file given with `--config`, or the one in the user's config folder if there
+/// is one.
+fn load_config(path: Option<&Path>) -> Result<Config, String> {
+    let (path, required) = match path {
+        Some(path) => (path.to_path_buf(), true),
+        None => match get_default_config_path() {
+            Some(path) => (path, false),
+            None => return Ok(Config::default())
+        }
+    };
+
+    match fs::read_to_string(&path) {
+        Ok(text) => Config::parse(&text).map_err(|err| format!("Error in {}: {}", path.display(), err)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound && !required => Ok(Config::default()),
+        Err(err) => Err(format!("Couldn't read {}: {}", path.display(), err))
+    }
+}
+
+/// Gets where the config file is kept: `%APPDATA%\ch8-rs\config.toml` on Windows, and
+/// `$XDG_CONFIG_HOME/ch8-rs/config.toml` or `~/.config/ch8-rs/config.toml` elsewhere.
+fn get_default_config_path() -> Option<PathBuf> {
+    let folder = if cfg!(windows) {
+        PathBuf::from(env::var_os("APPDATA")?)
+    } else {
+        env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?
+    };
+
+    Some(folder.join("ch8-rs").join("config.toml"))
+}
+
+/// Gets how the beeper should sound, using the defaults for anything not set on the command line
+/// or in the config file.
+fn create_beeper_settings(options: &Options) -> BeeperSettings {
+    let defaults = BeeperSettings::default();
 
-    window::create_window(cpu);
+    BeeperSettings {
+        waveform: options.waveform.unwrap_or(defaults.waveform),
+        frequency: options.frequency.unwrap_or(defaults.frequency),
+        volume: options.volume.unwrap_or(defaults.volume),
+        ramp: options.ramp.unwrap_or(defaults.ramp)
+    }
 }
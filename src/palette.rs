@@ -0,0 +1,86 @@
+/// Names accepted by [`Palette::parse`].
+pub const PALETTE_NAMES: [&str; 4] = ["default", "amber", "green", "lcd"];
+
+/// Colours for each combination of the two XO-CHIP planes: neither, first, second, both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    colors: [[u8; 4]; 4]
+}
+
+const DEFAULT: Palette = Palette {
+    colors: [
+        [20, 20, 20, 255],
+        [255, 255, 255, 255],
+        [255, 140, 0, 255],
+        [120, 60, 0, 255]
+    ]
+};
+
+impl Palette {
+    /// Looks up a built-in palette by one of the names in [`PALETTE_NAMES`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        let colors = match name {
+            "default" => return Some(DEFAULT),
+            "amber" => [0x1A1000, 0xFFB000, 0xFF6A00, 0x804000],
+            "green" => [0x0A1A0A, 0x33FF66, 0x1A9933, 0x0F4D1A],
+            "lcd" => [0x9BBC0F, 0x0F380F, 0x8BAC0F, 0x306230],
+            _ => return None
+        };
+
+        Some(Palette {
+            colors: colors.map(rgb_to_rgba)
+        })
+    }
+
+    /// Parses a palette name, or 2 or 4 comma-separated hex colours like `000000,FFFFFF`. With
+    /// two colours, the background and the first plane are set and the other XO-CHIP plane
+    /// colours are left as the default.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        if let Some(palette) = Palette::from_name(text) {
+            return Ok(palette);
+        }
+
+        let colors = text
+            .split(',')
+            .map(|color| {
+                let color = color.trim().trim_start_matches('#');
+
+                match u32::from_str_radix(color, 16) {
+                    Ok(rgb) if color.len() == 6 => Ok(rgb_to_rgba(rgb)),
+                    _ => Err(format!("{} isn't a hex colour like FF8C00", color))
+                }
+            })
+            .collect::<Result<Vec<[u8; 4]>, String>>()?;
+
+        let mut palette = DEFAULT;
+
+        match colors.len() {
+            2 | 4 => palette.colors[..colors.len()].copy_from_slice(&colors),
+            _ => {
+                return Err(format!(
+                    "A palette must be one of {}, or 2 or 4 hex colours separated by commas",
+                    PALETTE_NAMES.join(", ")
+                ));
+            }
+        }
+
+        Ok(palette)
+    }
+
+    /// Gets the RGBA colour for a pixel's plane bits, as returned by `DisplayBuffer::get_color_at`.
+    pub fn get_color(&self, index: u8) -> [u8; 4] {
+        self.colors[index as usize & 3]
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        DEFAULT
+    }
+}
+
+fn rgb_to_rgba(rgb: u32) -> [u8; 4] {
+    let [_, r, g, b] = rgb.to_be_bytes();
+
+    [r, g, b, 255]
+}
@@ -1,31 +1,75 @@
-use rodio::{source::SineWave, OutputStream, Sink};
+use ch8_core::audio::{self, AudioSink, Beeper, BeeperSettings, Tone};
+use rodio::{OutputStream, Sink, Source};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
+/// Plays the CPU's sound on the default output device with rodio.
+///
+/// The output stream keeps running while the CPU is silent, so the beeper can fade sound in
+/// and out instead of it cutting off with a click.
 pub struct Speaker {
     _stream: OutputStream,
-    sink: Sink
+    _sink: Sink,
+    beeper: Arc<Mutex<Beeper>>
 }
 
 impl Speaker {
-    pub fn new() -> Self {
-        let (_stream, stream_handle) = OutputStream::try_default().unwrap();
-        let sink = Sink::try_new(&stream_handle).unwrap();
+    /// Opens the default output device, failing if there isn't one that can be played to.
+    pub fn new(settings: BeeperSettings) -> Result<Self, String> {
+        let (_stream, stream_handle) = OutputStream::try_default()
+            .map_err(|err| format!("Couldn't open the sound device: {}", err))?;
+        let sink = Sink::try_new(&stream_handle).map_err(|err| format!("Couldn't play sound: {}", err))?;
+        let beeper = Arc::new(Mutex::new(Beeper::new(settings, audio::SAMPLE_RATE)));
 
-        sink.pause();
-        sink.append(SineWave::new(440));
+        sink.append(BeeperSource {
+            beeper: Arc::clone(&beeper)
+        });
 
-        Speaker {
+        Ok(Speaker {
             _stream,
-            sink
-        }
+            _sink: sink,
+            beeper
+        })
     }
+}
 
-    /// Starts the sound.
-    pub fn start(&self) {
-        self.sink.play();
+impl AudioSink for Speaker {
+    fn start(&mut self, _time: Duration, tone: Tone) {
+        self.beeper.lock().unwrap().play(Some(tone));
     }
 
-    /// Stops the sound.
-    pub fn stop(&self) {
-        self.sink.pause();
+    fn stop(&mut self, _time: Duration) {
+        self.beeper.lock().unwrap().play(None);
     }
-}
\ No newline at end of file
+}
+
+/// Endless source that plays whatever the beeper is playing.
+struct BeeperSource {
+    beeper: Arc<Mutex<Beeper>>
+}
+
+impl Iterator for BeeperSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        Some(self.beeper.lock().unwrap().next_sample())
+    }
+}
+
+impl Source for BeeperSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        audio::SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
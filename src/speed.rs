@@ -0,0 +1,76 @@
+use ch8_core::cpu;
+use std::time::{Duration, Instant};
+
+/// Slowest and fastest the instructions per frame can be set to with the speed keys.
+const MIN_INSTRUCTIONS_PER_FRAME: u32 = 1;
+const MAX_INSTRUCTIONS_PER_FRAME: u32 = 1000;
+
+/// Slow motion runs a frame every this many frame periods.
+pub const SLOW_MOTION_DIVISOR: u32 = 4;
+
+/// How long fast forward runs frames back to back before handing back to the event loop, so the
+/// window still redraws and handles input.
+pub const FAST_FORWARD_BUDGET: Duration = Duration::from_millis(12);
+
+/// How often the measured speed is updated.
+const MEASURE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Gets the next faster speed, about a quarter faster.
+pub fn faster(instructions_per_frame: u32) -> u32 {
+    (instructions_per_frame + 1)
+        .max(instructions_per_frame * 5 / 4)
+        .min(MAX_INSTRUCTIONS_PER_FRAME)
+}
+
+/// Gets the next slower speed, about a fifth slower.
+pub fn slower(instructions_per_frame: u32) -> u32 {
+    instructions_per_frame
+        .saturating_sub(1)
+        .min(instructions_per_frame * 4 / 5)
+        .max(MIN_INSTRUCTIONS_PER_FRAME)
+}
+
+/// Measures how fast emulated time passes compared to real time, as a percentage.
+pub struct SpeedMeter {
+    frames: u32,
+    since: Instant,
+    percent: u32
+}
+
+impl SpeedMeter {
+    pub fn new() -> Self {
+        SpeedMeter {
+            frames: 0,
+            since: Instant::now(),
+            percent: 100
+        }
+    }
+
+    /// Counts a frame that was run.
+    pub fn record_frame(&mut self) {
+        self.frames += 1;
+    }
+
+    /// Works out the speed if it's been long enough since the last time. Returns true if there's
+    /// a new measurement.
+    pub fn update(&mut self, now: Instant) -> bool {
+        let elapsed = now.duration_since(self.since);
+
+        if elapsed < MEASURE_INTERVAL {
+            return false;
+        }
+
+        let emulated = self.frames as f64 / cpu::TIMER_FREQUENCY_HZ as f64;
+
+        self.percent = (emulated / elapsed.as_secs_f64() * 100.0).round() as u32;
+        self.frames = 0;
+        self.since = now;
+
+        true
+    }
+
+    /// Gets the last measured speed, where 100 is full speed.
+    pub fn get_percent(&self) -> u32 {
+        self.percent
+    }
+}
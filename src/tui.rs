@@ -0,0 +1,370 @@
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::style::Print;
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use ch8_core::cpu::{self, Cpu};
+use ch8_core::debugger::{self, Debugger, StopReason};
+use ch8_core::disassembler;
+use ch8_core::wav::WavFileRecorder;
+use crate::keyboard;
+use crate::window;
+use std::io::{self, Stdout, Write};
+use std::time::{Duration, Instant};
+
+/// Terminals only report key presses, so a keypad key counts as held for this many frames after the
+/// last press. Key repeat keeps it held for as long as the key is down.
+const KEY_HOLD_FRAMES: u8 = 6;
+
+/// Instructions shown before the PC in the disassembly.
+const DISASSEMBLY_CONTEXT: usize = 6;
+
+const DISASSEMBLY_LINES: usize = 16;
+
+const MEMORY_ROWS: usize = 8;
+
+const HELP: &str = "F5 run/pause  F9 breakpoint  F10 step over  F11 step  F12 step out  \
+    Up/Down cursor  PgUp/PgDn memory  Home memory at I  Esc quit";
+
+/// Puts the terminal back how it was when dropped, even if drawing fails part way through.
+struct TerminalGuard;
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Debugger state shown around the display.
+struct View {
+    running: bool,
+    last_stop: Option<String>,
+    /// Disassembly cursor, or `None` to follow the PC.
+    cursor: Option<usize>,
+    /// First address of the memory view, or `None` to follow the index register.
+    memory_start: Option<usize>,
+    /// Display size last drawn, so the screen is cleared when the resolution changes.
+    last_size: (u8, u8)
+}
+
+/// Runs the interpreter in the terminal, with the display drawn in block characters next to
+/// the registers, stack, disassembly and memory. It starts paused.
+///
+/// The sound is only recorded if there's a WAV recorder. The terminal bell stands in for the
+/// beeper unless `bell` is false.
+pub fn run_tui(mut cpu: Cpu, wav: Option<WavFileRecorder>, bell: bool) -> crossterm::Result<()> {
+    let mut stdout = io::stdout();
+    let mut debugger = Debugger::new();
+    let frame_duration = Duration::from_secs(1) / cpu::TIMER_FREQUENCY_HZ;
+    let mut next_frame = Instant::now();
+    let mut key_timers = [0u8; 16];
+    let mut was_playing = false;
+    let mut view = View {
+        running: false,
+        last_stop: None,
+        cursor: None,
+        memory_start: None,
+        last_size: (0, 0)
+    };
+
+    if let Some(recorder) = wav.as_ref() {
+        cpu.set_audio_sink(Box::new(recorder.clone()));
+    }
+
+    terminal::enable_raw_mode()?;
+    let _guard = TerminalGuard;
+    execute!(stdout, EnterAlternateScreen, Hide, Clear(ClearType::All))?;
+
+    draw(&mut stdout, &cpu, &debugger, &mut view)?;
+
+    loop {
+        let timeout = next_frame.saturating_duration_since(Instant::now());
+
+        if event::poll(timeout)? {
+            match event::read()? {
+                Event::Key(key) => {
+                    if is_quit(&key) {
+                        break;
+                    }
+
+                    handle_key(key, &mut cpu, &mut debugger, &mut view, &mut key_timers);
+                }
+                Event::Resize(..) => {
+                    queue!(stdout, Clear(ClearType::All))?;
+                }
+                _ => {}
+            }
+
+            if !view.running {
+                draw(&mut stdout, &cpu, &debugger, &mut view)?;
+            }
+
+            continue;
+        }
+
+        next_frame += frame_duration;
+
+        // let go of keys once they stop repeating
+        for (key, timer) in key_timers.iter_mut().enumerate() {
+            if *timer > 0 {
+                *timer -= 1;
+
+                if *timer == 0 {
+                    cpu.get_keyboard().set_key_pressed(key as u8, false);
+                }
+            }
+        }
+
+        if view.running {
+            if let Some(reason) = debugger.run_frame(&mut cpu) {
+                stop(&mut view, &reason);
+            }
+
+            // only the latest fault fits in the status line, so say how many there were
+            let mut count = cpu.take_dropped_fault_count();
+
+            while let Some(fault) = cpu.take_recovered_fault() {
+                count += 1;
+                view.last_stop = Some(match count {
+                    1 => format!("recovered from {}", fault),
+                    _ => format!("recovered from {} faults, last {}", count, fault)
+                });
+            }
+
+            // ring the terminal bell as a stand-in for the beeper
+            let playing = cpu.is_sound_playing();
+
+            if bell && playing && !was_playing {
+                queue!(stdout, Print('\x07'))?;
+            }
+
+            was_playing = playing;
+
+            draw(&mut stdout, &cpu, &debugger, &mut view)?;
+        }
+    }
+
+    window::finish_recordings(&mut cpu, wav.as_ref());
+
+    Ok(())
+}
+
+fn is_quit(key: &KeyEvent) -> bool {
+    key.code == KeyCode::Esc || (key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL))
+}
+
+fn stop(view: &mut View, reason: &StopReason) {
+    view.running = false;
+    view.cursor = None;
+    view.last_stop = Some(reason.to_string());
+}
+
+fn handle_key(key: KeyEvent, cpu: &mut Cpu, debugger: &mut Debugger, view: &mut View, key_timers: &mut [u8; 16]) {
+    let stepped = match key.code {
+        KeyCode::F(5) => {
+            view.running = !view.running;
+            view.cursor = None;
+            view.last_stop = None;
+            None
+        }
+        KeyCode::F(9) => {
+            let addr = view.cursor.unwrap_or_else(|| cpu.get_program_counter());
+
+            if !debugger.remove_breakpoint(addr) {
+                debugger.add_breakpoint(addr);
+            }
+
+            None
+        }
+        KeyCode::F(10) if !view.running => Some(debugger.step_over(cpu, debugger::DEFAULT_STEP_LIMIT)),
+        KeyCode::F(11) if !view.running => Some(debugger.step(cpu)),
+        KeyCode::F(12) if !view.running => Some(debugger.step_out(cpu, debugger::DEFAULT_STEP_LIMIT)),
+        KeyCode::Up => {
+            view.cursor = Some(view.cursor.unwrap_or_else(|| cpu.get_program_counter()).saturating_sub(2));
+            None
+        }
+        KeyCode::Down => {
+            view.cursor = Some(view.cursor.unwrap_or_else(|| cpu.get_program_counter()) + 2);
+            None
+        }
+        KeyCode::PageUp => {
+            let start = view.memory_start.unwrap_or(cpu.get_index() as usize);
+
+            view.memory_start = Some(start.saturating_sub(MEMORY_ROWS * 16));
+            None
+        }
+        KeyCode::PageDown => {
+            let start = view.memory_start.unwrap_or(cpu.get_index() as usize);
+
+            view.memory_start = Some((start + MEMORY_ROWS * 16).min(cpu.get_memory().len() - 1));
+            None
+        }
+        KeyCode::Home => {
+            view.memory_start = None;
+            None
+        }
+        KeyCode::Char(c) => {
+            if let Some(key) = keyboard::get_key_from_char(c) {
+                cpu.get_keyboard().set_key_pressed(key, true);
+                key_timers[key as usize] = KEY_HOLD_FRAMES;
+            }
+
+            None
+        }
+        _ => None
+    };
+
+    if let Some(reason) = stepped {
+        stop(view, &reason);
+
+        if reason == StopReason::Step {
+            view.last_stop = None;
+        }
+    }
+}
+
+fn draw(stdout: &mut Stdout, cpu: &Cpu, debugger: &Debugger, view: &mut View) -> crossterm::Result<()> {
+    let display = cpu.get_display();
+    let size = (display.get_width(), display.get_height());
+
+    if size != view.last_size {
+        queue!(stdout, Clear(ClearType::All))?;
+        view.last_size = size;
+    }
+
+    // status line
+    let state = if view.running { "RUNNING" } else { "PAUSED " };
+    let status = match &view.last_stop {
+        Some(reason) => format!("{}  {}", state, reason),
+        None => state.to_string()
+    };
+
+    draw_lines(stdout, 0, 0, &[format!("{:<80}", status)])?;
+
+    // display, two pixel rows to a character
+    let display_lines: Vec<String> = (0..size.1)
+        .step_by(2)
+        .map(|y| {
+            (0..size.0)
+                .map(|x| {
+                    let top = display.get_color_at(x, y) > 0;
+                    let bottom = y + 1 < size.1 && display.get_color_at(x, y + 1) > 0;
+
+                    match (top, bottom) {
+                        (true, true) => '█',
+                        (true, false) => '▀',
+                        (false, true) => '▄',
+                        (false, false) => ' '
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    draw_lines(stdout, 0, 1, &display_lines)?;
+
+    // registers and stack to the right of the display
+    let mut registers: Vec<String> = cpu.get_registers()
+        .chunks(4)
+        .enumerate()
+        .map(|(row, values)| {
+            values
+                .iter()
+                .enumerate()
+                .map(|(i, val)| format!("V{:X} {:02X}", row * 4 + i, val))
+                .collect::<Vec<String>>()
+                .join("  ")
+        })
+        .collect();
+
+    registers.push(String::new());
+    registers.push(format!("I  {:03X}   PC {:03X}   SP {:X}", cpu.get_index(), cpu.get_program_counter(), cpu.get_stack().len()));
+    registers.push(format!("DT {:02X}    ST {:02X}", cpu.get_delay_timer(), cpu.get_sound_timer()));
+    registers.push(String::new());
+    registers.push(String::from("Stack"));
+
+    for (depth, addr) in cpu.get_stack().iter().enumerate().rev() {
+        registers.push(format!("{:X}: {:03X}", depth, addr));
+    }
+
+    // pad to the size of a full stack to clear lines left over from a deeper one
+    registers.resize(9 + 16, String::new());
+
+    let registers: Vec<String> = registers.iter().map(|line| format!("{:<30}", line)).collect();
+
+    draw_lines(stdout, size.0 as u16 + 2, 1, &registers)?;
+
+    // disassembly and memory below the display
+    let top = 1 + display_lines.len() as u16 + 1;
+
+    draw_lines(stdout, 0, top, &disassembly_lines(cpu, debugger, view))?;
+    draw_lines(stdout, 40, top, &memory_lines(cpu, view))?;
+    draw_lines(stdout, 0, top + DISASSEMBLY_LINES as u16 + 1, &[String::from(HELP)])?;
+
+    stdout.flush()?;
+
+    Ok(())
+}
+
+/// Disassembles around the cursor, marking the PC with `>`, the cursor with `-` and breakpoints with `*`.
+fn disassembly_lines(cpu: &Cpu, debugger: &Debugger, view: &View) -> Vec<String> {
+    let pc = cpu.get_program_counter();
+    let center = view.cursor.unwrap_or(pc);
+    let start = center.saturating_sub(DISASSEMBLY_CONTEXT * 2);
+    let memory = cpu.get_memory();
+    let end = (start + DISASSEMBLY_LINES * 2).min(memory.len());
+    let opcodes = disassembler::disassemble_bytes(&memory[start.min(end)..end]);
+
+    (0..DISASSEMBLY_LINES)
+        .map(|i| {
+            let addr = start + i * 2;
+            let marker = if addr == pc {
+                '>'
+            } else if Some(addr) == view.cursor {
+                '-'
+            } else {
+                ' '
+            };
+            let breakpoint = if debugger.has_breakpoint(addr) { '*' } else { ' ' };
+            let line = match opcodes.get(i) {
+                Some(Some(opcode)) => format!("{}{}{:03X}: {:04X}  {:?}", marker, breakpoint, addr, debugger::read_word(cpu, addr), opcode),
+                Some(None) => format!("{}{}{:03X}: {:04X}  ???", marker, breakpoint, addr, debugger::read_word(cpu, addr)),
+                None => String::new()
+            };
+
+            format!("{:<38}", line)
+        })
+        .collect()
+}
+
+/// Hex dump starting at the index register, or wherever the view was scrolled to.
+fn memory_lines(cpu: &Cpu, view: &View) -> Vec<String> {
+    let memory = cpu.get_memory();
+    let start = view.memory_start.unwrap_or(cpu.get_index() as usize) & !0xF;
+
+    (0..MEMORY_ROWS)
+        .map(|row| {
+            let addr = start + row * 16;
+
+            if addr >= memory.len() {
+                return format!("{:<54}", "");
+            }
+
+            let bytes: Vec<String> = memory[addr..(addr + 16).min(memory.len())]
+                .iter()
+                .map(|byte| format!("{:02X}", byte))
+                .collect();
+
+            format!("{:04X}: {:<48}", addr, bytes.join(" "))
+        })
+        .collect()
+}
+
+fn draw_lines(stdout: &mut Stdout, x: u16, y: u16, lines: &[String]) -> crossterm::Result<()> {
+    for (i, line) in lines.iter().enumerate() {
+        queue!(stdout, MoveTo(x, y + i as u16), Print(line))?;
+    }
+
+    Ok(())
+}
@@ -6,20 +6,136 @@ use winit::event_loop::{EventLoop, ControlFlow};
 use winit::platform::windows::WindowBuilderExtWindows;
 use winit::window::WindowBuilder;
 use winit_input_helper::WinitInputHelper;
-use crate::cpu::Cpu;
-use crate::display;
-use crate::keyboard;
+use ch8_core::audio::{BeeperSettings, TeeSink};
+use ch8_core::cpu::{self, Cpu};
+use ch8_core::debugger::{Debugger, StopReason};
+use ch8_core::display;
+use ch8_core::gdb::{GdbStub, TargetState};
+use ch8_core::rewind::RewindBuffer;
+use ch8_core::wav::WavFileRecorder;
+use crate::debug_prompt::{self, DebugPrompt};
+use crate::keyboard::KeyMap;
+use crate::palette::Palette;
+use crate::speaker::Speaker;
+use crate::speed::{self, SpeedMeter};
 use pixels::wgpu::PresentMode;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-pub fn create_window(mut cpu: Cpu) {
+/// Number of displayed frames kept for rewinding, 10 seconds at 60 frames per second.
+const REWIND_FRAMES: usize = 600;
+
+/// Key held to step backwards through time.
+const REWIND_KEY: VirtualKeyCode = VirtualKeyCode::Back;
+
+/// Key that pauses execution and opens the debugger prompt in the terminal.
+const DEBUG_KEY: VirtualKeyCode = VirtualKeyCode::F12;
+
+/// Keys that raise and lower the instructions per frame.
+const FASTER_KEY: VirtualKeyCode = VirtualKeyCode::Equals;
+const SLOWER_KEY: VirtualKeyCode = VirtualKeyCode::Minus;
+
+/// Key held to run as fast as possible.
+const FAST_FORWARD_KEY: VirtualKeyCode = VirtualKeyCode::Tab;
+
+/// Key that toggles running at a quarter of the frame rate.
+const SLOW_MOTION_KEY: VirtualKeyCode = VirtualKeyCode::F6;
+
+/// Keys for the save state slots. Pressing one loads the slot, holding shift saves to it.
+const SAVE_SLOT_KEYS: [VirtualKeyCode; 4] = [
+    VirtualKeyCode::F1,
+    VirtualKeyCode::F2,
+    VirtualKeyCode::F3,
+    VirtualKeyCode::F4
+];
+
+/// Every key the window handles itself, which can't also be bound to a CHIP-8 key.
+pub const HOTKEYS: [VirtualKeyCode; 11] = [
+    VirtualKeyCode::Escape,
+    REWIND_KEY,
+    DEBUG_KEY,
+    FASTER_KEY,
+    SLOWER_KEY,
+    FAST_FORWARD_KEY,
+    SLOW_MOTION_KEY,
+    SAVE_SLOT_KEYS[0],
+    SAVE_SLOT_KEYS[1],
+    SAVE_SLOT_KEYS[2],
+    SAVE_SLOT_KEYS[3]
+];
+
+/// How the window looks and sounds, and what's attached to it.
+pub struct WindowOptions {
+    /// Shown in the title bar, e.g. the ROM's title.
+    pub title: String,
+    /// Save states are stored next to the ROM.
+    pub rom_path: PathBuf,
+    /// Window size as a multiple of the 64x32 display.
+    pub scale: u32,
+    pub palette: Palette,
+    pub keys: KeyMap,
+    /// Start at the debugger prompt instead of running.
+    pub start_paused: bool,
+    /// How the beeper sounds, or `None` to play no sound.
+    pub beeper: Option<BeeperSettings>,
+    pub gdb: Option<GdbStub>,
+    pub wav: Option<WavFileRecorder>
+}
+
+pub fn create_window(mut cpu: Cpu, options: WindowOptions) {
+    let WindowOptions {
+        title,
+        rom_path,
+        scale,
+        palette,
+        keys,
+        start_paused,
+        beeper,
+        mut gdb,
+        wav
+    } = options;
     let ev_loop = EventLoop::new();
     let mut input = WinitInputHelper::new();
+    let frame_duration = Duration::from_secs(1) / cpu::TIMER_FREQUENCY_HZ;
+    let mut next_frame = Instant::now();
+    let mut rewind = RewindBuffer::new(REWIND_FRAMES);
+    let mut rewinding = false;
+    let mut debugger = Debugger::new();
+    let mut prompt = DebugPrompt::new();
+    let mut paused = start_paused;
+    let mut meter = SpeedMeter::new();
+    let mut fast_forward = false;
+    let mut slow_motion = false;
+    let mut title_changed = false;
+
+    // carry on without sound if there's no output device
+    let speaker = beeper.and_then(|settings| match Speaker::new(settings) {
+        Ok(speaker) => Some(speaker),
+        Err(err) => {
+            eprintln!("{}, running without sound", err);
+            None
+        }
+    });
+
+    match (speaker, wav.as_ref()) {
+        (Some(speaker), Some(recorder)) => {
+            cpu.set_audio_sink(Box::new(TeeSink::new(Box::new(speaker), Box::new(recorder.clone()))));
+        }
+        (Some(speaker), None) => cpu.set_audio_sink(Box::new(speaker)),
+        (None, Some(recorder)) => cpu.set_audio_sink(Box::new(recorder.clone())),
+        (None, None) => {}
+    }
+
+    if paused {
+        debug_prompt::report(&cpu, &StopReason::Step);
+    }
 
     let window = {
-        let multiplier = 15;
-        let size = LogicalSize::new(display::WIDTH as u32 * multiplier, display::HEIGHT as u32 * multiplier);
+        let size = LogicalSize::new(display::WIDTH as u32 * scale, display::HEIGHT as u32 * scale);
+        #[cfg_attr(not(windows), allow(unused_mut))]
         let mut builder = WindowBuilder::new()
-            .with_title("CHIP-8 Interpreter")
+            .with_title(get_title(&title, &cpu, &meter, fast_forward, slow_motion))
             .with_inner_size(size)
             .with_min_inner_size(size);
 
@@ -37,7 +153,8 @@ pub fn create_window(mut cpu: Cpu) {
         let inner = window.inner_size();
         let texture = SurfaceTexture::new(inner.width, inner.height, &window);
 
-        PixelsBuilder::new(display::WIDTH as u32, display::HEIGHT as u32, texture)
+        // the frame is always high resolution, and low resolution pixels are scaled up to fit
+        PixelsBuilder::new(display::HIRES_WIDTH as u32, display::HIRES_HEIGHT as u32, texture)
             .present_mode(PresentMode::Mailbox)
             .build()
             .unwrap()
@@ -50,14 +167,15 @@ pub fn create_window(mut cpu: Cpu) {
                 let frame = pixels.get_frame();
                 let display = cpu.get_display();
 
+                let scale = (display::HIRES_WIDTH / display.get_width()) as usize;
+
                 for (i, pixel) in frame.chunks_exact_mut(4).enumerate() {
-                    let new_pixel = if display.is_set(i) {
-                        [255, 255, 255, 255]
-                    } else {
-                        [20, 20, 20, 255]
-                    };
+                    let x = (i % display::HIRES_WIDTH as usize) / scale;
+                    let y = (i / display::HIRES_WIDTH as usize) / scale;
+
+                    let color = display.get_color_at(x as u8, y as u8);
 
-                    pixel.copy_from_slice(&new_pixel);
+                    pixel.copy_from_slice(&palette.get_color(color));
                 }
 
                 pixels.render().unwrap();
@@ -68,10 +186,9 @@ pub fn create_window(mut cpu: Cpu) {
 
         // handle input events
         if input.update(&event) {
-            let keyboard = cpu.get_keyboard();
-
             // close events
             if input.key_pressed(VirtualKeyCode::Escape) || input.quit() {
+                finish_recordings(&mut cpu, wav.as_ref());
                 *control_flow = ControlFlow::Exit;
                 return;
             }
@@ -81,19 +198,227 @@ pub fn create_window(mut cpu: Cpu) {
                 pixels.resize(size.width, size.height);
             }
 
+            rewinding = input.key_held(REWIND_KEY);
+
+            // speed controls
+            if input.key_pressed(FASTER_KEY) {
+                cpu.set_instructions_per_frame(speed::faster(cpu.get_instructions_per_frame()));
+                title_changed = true;
+            } else if input.key_pressed(SLOWER_KEY) {
+                cpu.set_instructions_per_frame(speed::slower(cpu.get_instructions_per_frame()));
+                title_changed = true;
+            }
+
+            if input.key_pressed(SLOW_MOTION_KEY) {
+                slow_motion = !slow_motion;
+                title_changed = true;
+            }
+
+            if input.key_held(FAST_FORWARD_KEY) != fast_forward {
+                fast_forward = !fast_forward;
+                title_changed = true;
+            }
+
+            if input.key_pressed(DEBUG_KEY) && !paused {
+                paused = true;
+                debug_prompt::report(&cpu, &StopReason::Step);
+            }
+
+            // save states
+            for (slot, &key_code) in SAVE_SLOT_KEYS.iter().enumerate() {
+                if input.key_pressed(key_code) {
+                    let path = get_state_path(&rom_path, slot + 1);
+
+                    if input.held_shift() {
+                        save_state(&cpu, &path);
+                    } else {
+                        load_state(&mut cpu, &path);
+                    }
+                }
+            }
+
             // other key inputs
+            let keyboard = cpu.get_keyboard();
+
             for key in 0x0..=0xF_u8 {
-                if let Some(key_code) = keyboard::get_keycode_from_key(key) {
-                    if input.key_pressed(key_code) {
-                        keyboard.set_key_pressed(key, true);
-                    } else if input.key_released(key_code) {
-                        keyboard.set_key_pressed(key, false);
+                let key_code = keys.get_keycode(key);
+
+                if input.key_pressed(key_code) {
+                    keyboard.set_key_pressed(key, true);
+                } else if input.key_released(key_code) {
+                    keyboard.set_key_pressed(key, false);
+                }
+            }
+        }
+
+        // SUPER-CHIP programs can exit on their own
+        if cpu.has_exited() {
+            finish_recordings(&mut cpu, wav.as_ref());
+            *control_flow = ControlFlow::Exit;
+            return;
+        }
+
+        // let an attached gdb inspect and control the CPU
+        if let Some(stub) = gdb.as_mut() {
+            if let Err(err) = stub.poll(&mut cpu, &mut debugger) {
+                eprintln!("GDB connection error: {}", err);
+            }
+
+            if stub.get_state() == TargetState::Killed {
+                finish_recordings(&mut cpu, wav.as_ref());
+                *control_flow = ControlFlow::Exit;
+                return;
+            }
+        }
+
+        let gdb_attached = gdb.as_ref().is_some_and(GdbStub::is_connected);
+        let gdb_stopped = gdb.as_ref().is_some_and(|stub| stub.get_state() == TargetState::Stopped);
+
+        // run the interpreter at a fixed frame rate
+        let now = Instant::now();
+
+        if next_frame <= now || (fast_forward && !paused) {
+            if paused {
+                // the prompt blocks, so hand back to the event loop after every step to redraw
+                paused = prompt.prompt(&mut cpu, &mut debugger);
+            } else if rewinding {
+                // step backwards a frame at a time while the rewind key is held
+                if let Some(state) = rewind.pop() {
+                    if let Err(err) = cpu.load_state(&state) {
+                        eprintln!("Couldn't rewind: {}", err);
+                    }
+                }
+            } else if gdb_stopped {
+                // gdb is in control, and steps the CPU itself
+            } else if cpu.get_halt_fault().is_none() || gdb_attached {
+                let deadline = now + speed::FAST_FORWARD_BUDGET;
+
+                // fast forward runs frames back to back, and the timers still count down once a
+                // frame so they keep pace with the program
+                loop {
+                    paused = run_frame(&mut cpu, &mut debugger, gdb.as_mut());
+                    meter.record_frame();
+
+                    let stopped = paused
+                        || cpu.has_exited()
+                        || cpu.get_halt_fault().is_some()
+                        || gdb.as_ref().is_some_and(|stub| stub.get_state() == TargetState::Stopped);
+
+                    if !fast_forward || stopped || Instant::now() >= deadline {
+                        break;
                     }
                 }
+
+                // keep what was shown rather than every frame run, so fast forwarding doesn't
+                // use up the rewind buffer
+                rewind.push(cpu.save_state());
+            }
+
+            // schedule from the last deadline so lateness doesn't add up, but don't try to catch
+            // up on frames missed while paused, fast forwarding or blocked
+            next_frame += frame_duration * if slow_motion { speed::SLOW_MOTION_DIVISOR } else { 1 };
+
+            if next_frame < now {
+                next_frame = now;
             }
         }
 
-        // interpreter tick
-        cpu.tick();
+        if meter.update(now) || title_changed {
+            window.set_title(&get_title(&title, &cpu, &meter, fast_forward, slow_motion));
+            title_changed = false;
+        }
     });
-}
\ No newline at end of file
+}
+
+/// Runs a frame, reporting why it stopped early if it did. Returns true if the debugger prompt
+/// should open.
+fn run_frame(cpu: &mut Cpu, debugger: &mut Debugger, gdb: Option<&mut GdbStub>) -> bool {
+    let mut pause = false;
+
+    match (debugger.run_frame(cpu), gdb.filter(|stub| stub.is_connected())) {
+        (Some(reason), Some(stub)) => {
+            if let Err(err) = stub.report_stop(cpu, &reason) {
+                eprintln!("GDB connection error: {}", err);
+            }
+        }
+        (Some(StopReason::Fault(fault)), None) => eprintln!("CPU halted: {}", fault),
+        (Some(StopReason::Exited), None) | (None, _) => {}
+        (Some(reason), None) => {
+            debug_prompt::report(cpu, &reason);
+            pause = true;
+        }
+    }
+
+    while let Some(fault) = cpu.take_recovered_fault() {
+        eprintln!("Recovered from {}", fault);
+    }
+
+    match cpu.take_dropped_fault_count() {
+        0 => {}
+        dropped => eprintln!("Recovered from {} more faults", dropped)
+    }
+
+    pause
+}
+
+/// Gets the window title, with how fast the interpreter is set to run and how fast it's running.
+fn get_title(title: &str, cpu: &Cpu, meter: &SpeedMeter, fast_forward: bool, slow_motion: bool) -> String {
+    let instructions = cpu.get_instructions_per_frame();
+    let mode = if fast_forward {
+        " - fast forward"
+    } else if slow_motion {
+        " - slow motion"
+    } else {
+        ""
+    };
+
+    format!(
+        "{} - {} instructions/frame ({} Hz) - {}%{}",
+        title,
+        instructions,
+        instructions * cpu::TIMER_FREQUENCY_HZ,
+        meter.get_percent(),
+        mode
+    )
+}
+
+/// Gets the file a save state slot is stored in, next to the ROM.
+fn get_state_path(rom_path: &Path, slot: usize) -> PathBuf {
+    let mut path = rom_path.as_os_str().to_owned();
+
+    path.push(format!(".state{}", slot));
+
+    PathBuf::from(path)
+}
+
+/// Writes the machine state to a file, reporting any error.
+fn save_state(cpu: &Cpu, path: &Path) {
+    match fs::write(path, cpu.save_state()) {
+        Ok(()) => println!("Saved state to {}", path.display()),
+        Err(err) => eprintln!("Couldn't save state to {}: {}", path.display(), err)
+    }
+}
+
+/// Restores the machine state from a file, reporting any error.
+fn load_state(cpu: &mut Cpu, path: &Path) {
+    let result = fs::read(path)
+        .map_err(|err| err.to_string())
+        .and_then(|state| cpu.load_state(&state).map_err(|err| err.to_string()));
+
+    match result {
+        Ok(()) => println!("Loaded state from {}", path.display()),
+        Err(err) => eprintln!("Couldn't load state from {}: {}", path.display(), err)
+    }
+}
+
+/// Flushes the instruction trace and completes the WAV recording before exiting, since the
+/// event loop never returns.
+pub fn finish_recordings(cpu: &mut Cpu, wav: Option<&WavFileRecorder>) {
+    if let Some(Err(err)) = cpu.take_tracer().map(|tracer| tracer.finish()) {
+        eprintln!("Couldn't write trace: {}", err);
+    }
+
+    if let Some(Err(err)) = wav.map(|recorder| recorder.finish(cpu.get_elapsed_time())) {
+        eprintln!("Couldn't write WAV recording: {}", err);
+    }
+}